@@ -0,0 +1,99 @@
+//! Compares starting headless Chrome once per document with sharing one
+//! [`Renderer`] across a batch, on a synthetic corpus.
+//!
+//! Needs Chrome or Chromium to be installed. Run with
+//! `cargo bench --bench renderer`.
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use std::env;
+use std::path::{Path, PathBuf};
+
+use pdf_ok::utils::{
+    generate_pdf, render_markdown, FrontMatter, FrontMatterValue,
+    MarkdownOptions, MetadataOptions, PageSetup, Renderer,
+};
+
+// The number of documents in the synthetic corpus
+const CORPUS_SIZE: usize = 10;
+
+// Builds the HTML and front matter of one synthetic document.
+fn synthetic_document(index: usize) -> (String, FrontMatter) {
+    let mut markdown = format!("# Document {}\n\n", index);
+    for section in 1..=5 {
+        markdown.push_str(&format!(
+            "## Section {}\n\nSome *text* with a [link](https://kaishi.one).\n\n\
+             | a | b |\n| - | - |\n| {} | {} |\n\n",
+            section, index, section
+        ));
+    }
+    let html = render_markdown(&markdown, &MarkdownOptions::default());
+
+    let front_matter = [
+        ("title", format!("Document {}", index)),
+        ("author", "Jane Doe".to_string()),
+        ("description", "A synthetic benchmark document".to_string()),
+        ("keywords", "benchmark, pdf".to_string()),
+        ("language", "en-GB".to_string()),
+        ("permalink", "https://kaishi.one".to_string()),
+        ("site_components", "none".to_string()),
+        ("generator", "pdf_ok benchmark".to_string()),
+    ]
+    .into_iter()
+    .map(|(key, value)| (key.to_string(), FrontMatterValue::String(value)))
+    .collect();
+
+    (html, front_matter)
+}
+
+// Prints every document of the corpus, with the shared renderer if there
+// is one, otherwise starting Chrome for each document.
+fn render_corpus(
+    corpus: &[(String, FrontMatter)],
+    output_directory: &Path,
+    shared: Option<&Renderer>,
+) {
+    for (index, (html, front_matter)) in corpus.iter().enumerate() {
+        let source = PathBuf::from(format!("document_{}.md", index));
+        let pdf_path = output_directory.join(format!("document_{}.pdf", index));
+        let own_renderer;
+        let renderer = match shared {
+            Some(renderer) => renderer,
+            None => {
+                own_renderer = Renderer::new();
+                &own_renderer
+            }
+        };
+        generate_pdf(
+            renderer,
+            html.clone(),
+            &source,
+            &pdf_path,
+            front_matter.clone(),
+            &PageSetup::default(),
+            &MetadataOptions::default(),
+        )
+        .unwrap();
+    }
+}
+
+fn renderer_benchmark(c: &mut Criterion) {
+    let corpus: Vec<_> = (0..CORPUS_SIZE).map(synthetic_document).collect();
+    let output_directory = env::temp_dir().join("pdf_ok_renderer_bench");
+
+    let mut group = c.benchmark_group("render_corpus");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(CORPUS_SIZE as u64));
+
+    group.bench_function("browser_per_document", |b| {
+        b.iter(|| render_corpus(&corpus, &output_directory, None))
+    });
+    group.bench_function("shared_renderer", |b| {
+        let renderer = Renderer::new();
+        b.iter(|| render_corpus(&corpus, &output_directory, Some(&renderer)));
+        renderer.shutdown();
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, renderer_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,123 @@
+use std::path::PathBuf;
+
+/// The errors that can occur while turning a source file into a PDF.
+///
+/// Every variant that relates to a single document carries the path of
+/// the source file, so a batch can report exactly which documents
+/// failed and why.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// A file or directory could not be read or written.
+    #[error("{}: {source}", path.display())]
+    Io {
+        /// The path being read or written.
+        path: PathBuf,
+        /// The underlying I/O error.
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// The front matter could not be parsed.
+    #[error("{}: invalid front matter: {message}", path.display())]
+    FrontMatter {
+        /// The source file containing the front matter.
+        path: PathBuf,
+        /// A description of the problem.
+        message: String,
+    },
+
+    /// A placeholder in the document could not be replaced.
+    #[error("{}:{line}: {message}", path.display())]
+    Template {
+        /// The source file containing the placeholder.
+        path: PathBuf,
+        /// The 1-based line of the source file the placeholder is on.
+        line: usize,
+        /// A description of the problem.
+        message: String,
+    },
+
+    /// A front matter key needed for the PDF metadata is missing.
+    #[error("{}: missing front matter key `{key}`", path.display())]
+    MissingMetadataKey {
+        /// The source file missing the key.
+        path: PathBuf,
+        /// The front matter key that was looked up.
+        key: String,
+    },
+
+    /// The theme of a document could not be found or loaded.
+    #[error("{}: invalid theme: {message}", path.display())]
+    Theme {
+        /// The source file that uses the theme.
+        path: PathBuf,
+        /// A description of the problem.
+        message: String,
+    },
+
+    /// The paper size, orientation or margins of a document are not
+    /// valid.
+    #[error("{}: invalid page setup: {message}", path.display())]
+    PageSetup {
+        /// The source file whose front matter sets the page.
+        path: PathBuf,
+        /// A description of the problem.
+        message: String,
+    },
+
+    /// The page layout, page mode or zoom of a document is not valid.
+    #[error("{}: invalid viewer preferences: {message}", path.display())]
+    ViewerPreferences {
+        /// The source file whose front matter sets the preferences.
+        path: PathBuf,
+        /// A description of the problem.
+        message: String,
+    },
+
+    /// The path of the output PDF could not be worked out.
+    #[error("{}: {message}", path.display())]
+    OutputPath {
+        /// The source file being rendered.
+        path: PathBuf,
+        /// A description of the problem.
+        message: String,
+    },
+
+    /// The headless Chrome browser could not be started.
+    #[error("{}: could not launch headless Chrome: {message}", path.display())]
+    BrowserLaunch {
+        /// The source file that was to be rendered.
+        path: PathBuf,
+        /// The error reported by the browser.
+        message: String,
+    },
+
+    /// Headless Chrome failed to load or print a document.
+    #[error("{}: could not render the PDF: {message}", path.display())]
+    Render {
+        /// The source file being rendered.
+        path: PathBuf,
+        /// The error reported by the browser.
+        message: String,
+    },
+
+    /// The PDF printed by Chrome could not be loaded or updated.
+    #[error("{}: could not post-process the PDF: {source}", path.display())]
+    PdfPostProcess {
+        /// The source file being rendered.
+        path: PathBuf,
+        /// The underlying lopdf error.
+        #[source]
+        source: lopdf::Error,
+    },
+
+    /// The finished PDF could not be written to disk.
+    #[error("{}: could not save the PDF: {source}", path.display())]
+    Save {
+        /// The output PDF file.
+        path: PathBuf,
+        /// The underlying I/O error.
+        #[source]
+        source: std::io::Error,
+    },
+}
@@ -9,26 +9,33 @@ use colored::*; // Import the colored crate for colourful console output
 use dotenvy::dotenv; // Import the dotenvy crate for loading environment variables from a .env file
 use std::env;
 
+/// `error` module contains the error type returned when a document fails.
+mod error;
+pub use error::Error;
+
 /// `utils` module contains utility functions used throughout the application.
 pub mod utils;
-use crate::utils::{get_source_front_matter_files, read_file_data};
+use crate::utils::{
+    get_source_front_matter_files, read_file_data, BuildConfig,
+};
 
 /// This is the main entry point for the PDF from Front Matter YAML package/crate.
 ///
 /// # Errors
 ///
-/// Returns a boxed error if there is an issue reading the .env file or if the TEST_MODE is set to "1".
+/// Returns a boxed error if the TEST_MODE is set to "1", if the source files cannot be found,
+/// or if any document fails to generate. A missing .env file is not an error.
 ///
 /// # Examples
 ///
-/// ```
+/// ```no_run
 /// use pdf_ok::run;
 ///
 /// let result = run();
 /// assert!(result.is_ok());
 /// ```
 pub fn run() -> Result<(), Box<dyn std::error::Error>> {
-    dotenv().expect(".env file not found"); // Load environment variables from .env file
+    dotenv().ok(); // Load environment variables from a .env file, if there is one
 
     // Check if TEST_MODE is set to "1" in the .env file
     if env::var("TEST_MODE").unwrap_or_default() == "1" {
@@ -42,13 +49,28 @@ pub fn run() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     // Fetch the project name from the environment variables
-    let name = env::var("PROJECT_NAME").unwrap().to_string();
+    let name = env::var("PROJECT_NAME")
+        .unwrap_or_else(|_| env!("CARGO_PKG_NAME").to_string());
     println!("\nWelcome to {}", name.yellow());
     println!("PDF creation from Front Matter (YAML) files.");
 
     // Retrieve Front Matter YAML (fmy) source files and read their data
-    let fmy_source_files = get_source_front_matter_files();
-    read_file_data(fmy_source_files);
+    let fmy_source_files = get_source_front_matter_files()?;
+    let results = read_file_data(fmy_source_files, &BuildConfig::default());
+
+    // Report every failed document, then fail the run if there were any
+    let failed_count = results.iter().filter(|result| result.is_err()).count();
+    for error in results.iter().filter_map(|result| result.as_ref().err()) {
+        eprintln!("{} {}", "error".bright_red(), error);
+    }
+    if failed_count > 0 {
+        return Err(format!(
+            "{} of {} documents failed",
+            failed_count,
+            results.len()
+        )
+        .into());
+    }
 
 // TESTING
     // let something = "bob";
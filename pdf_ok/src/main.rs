@@ -2,16 +2,293 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
 //! This is the main entry point for the PDF from Front Matter YAML package/crate.
+use clap::{Args, Parser, Subcommand};
+use colored::Colorize;
 use dotenvy::dotenv;
+use pdf_ok::Error;
+use pdf_ok::utils::{
+    check_file_data, discover_source_files, inspect_pdf, read_file_data,
+    source_date_epoch, watch_source_files,
+    BuildConfig, DocInfoEntry, Margins, MissingKeyPolicy, PageSetup, PaperOrientation,
+    PaperSize, DEFAULT_FILENAME_TEMPLATE, DEFAULT_LAYOUTS_DIRECTORY,
+    DEFAULT_MAX_TABS, DEFAULT_OUTPUT_DIRECTORY,
+    DEFAULT_SOURCE_DIRECTORY, DEFAULT_THEME, DEFAULT_THEMES_DIRECTORY,
+    XmpNamespace, XmpProperty,
+};
 use std::env;
+use std::path::PathBuf;
+
+/// Generate PDF documents from Markdown files with Front Matter (YAML).
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Generate PDFs from the given files and directories.
+    Build {
+        #[command(flatten)]
+        source_args: SourceArgs,
+        #[command(flatten)]
+        build_args: BuildArgs,
+    },
+    /// Check the front matter, placeholders, layouts and output file
+    /// names without generating PDFs.
+    Check {
+        #[command(flatten)]
+        source_args: SourceArgs,
+        #[command(flatten)]
+        build_args: BuildArgs,
+    },
+    /// Build, then rebuild the affected PDFs whenever a source file,
+    /// layout or theme changes.
+    Watch {
+        #[command(flatten)]
+        source_args: SourceArgs,
+        #[command(flatten)]
+        build_args: BuildArgs,
+    },
+    /// Print the version, page count and metadata of a PDF file.
+    Inspect {
+        /// The PDF file to inspect.
+        pdf: PathBuf,
+    },
+}
+
+#[derive(Args)]
+struct SourceArgs {
+    /// Files or directories to read. Defaults to `./source_files`.
+    paths: Vec<PathBuf>,
+    /// Glob pattern of files to include, relative to each directory.
+    #[arg(long = "include", value_name = "GLOB")]
+    include_patterns: Vec<String>,
+    /// Glob pattern of files to leave out, relative to each directory.
+    #[arg(long = "exclude", value_name = "GLOB")]
+    exclude_patterns: Vec<String>,
+}
+
+#[derive(Args)]
+struct BuildArgs {
+    /// Text placed between list items written by a placeholder.
+    #[arg(long, value_name = "TEXT", default_value = ", ")]
+    list_separator: String,
+    /// What to do with a placeholder whose key is not in the front matter:
+    /// keep, empty, warn or fail.
+    #[arg(long, value_name = "POLICY", default_value = "keep")]
+    missing_keys: MissingKeyPolicy,
+    /// Markdown extension to switch off: tables, task-lists,
+    /// strikethrough, footnotes, autolinks or raw-html.
+    #[arg(long = "disable-extension", value_name = "NAME")]
+    disabled_extensions: Vec<String>,
+    /// Directory of HTML layouts, chosen by the `layout` front matter key.
+    #[arg(long, value_name = "DIR", default_value = DEFAULT_LAYOUTS_DIRECTORY)]
+    layouts_dir: PathBuf,
+    /// Theme for documents without a `theme` front matter key, or `none`.
+    #[arg(long, value_name = "NAME", default_value = DEFAULT_THEME)]
+    theme: String,
+    /// Directory of CSS themes.
+    #[arg(long, value_name = "DIR", default_value = DEFAULT_THEMES_DIRECTORY)]
+    themes_dir: PathBuf,
+    /// Paper size: A0-A10, B0-B10, Letter, Legal or WIDTHxHEIGHT, e.g.
+    /// `100mmx150mm`.
+    #[arg(long, value_name = "SIZE", default_value = "A4")]
+    paper_size: PaperSize,
+    /// Paper orientation: portrait or landscape.
+    #[arg(long, value_name = "ORIENTATION", default_value = "portrait")]
+    orientation: PaperOrientation,
+    /// Page margins as one to four lengths in mm, in or pt, in CSS order,
+    /// e.g. `"20mm 15mm"`. Numbers without a unit are millimetres.
+    #[arg(long, value_name = "MARGINS", default_value = "0.4in")]
+    margins: Margins,
+    /// Directory the PDFs are written to, mirroring the source directories.
+    #[arg(long, value_name = "DIR", default_value = DEFAULT_OUTPUT_DIRECTORY)]
+    output_dir: PathBuf,
+    /// File name of each PDF, using front matter placeholders and
+    /// `file_stem`, e.g. `"{{date}}-{{short_name}}.pdf"`.
+    #[arg(long, value_name = "TEMPLATE", default_value = DEFAULT_FILENAME_TEMPLATE)]
+    filename_template: String,
+    /// Number of documents processed at the same time. Defaults to the
+    /// number of CPUs.
+    #[arg(long, short, value_name = "N")]
+    jobs: Option<usize>,
+    /// Number of Chrome tabs open at the same time.
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_MAX_TABS)]
+    max_tabs: usize,
+    /// Info dictionary entry to write from a front matter key, e.g.
+    /// `Subject=summary`. Replaces the default mapping for that entry.
+    /// Append `:skip` (the default), `:fail` or `:default=VALUE` to
+    /// choose what happens when the key is missing.
+    #[arg(long = "doc-info", value_name = "INFO=KEY[:POLICY]")]
+    doc_info_entries: Vec<DocInfoEntry>,
+    /// Namespace for custom XMP properties, e.g.
+    /// `kaishi=https://kaishi.example/ns/1.0/`.
+    #[arg(long = "xmp-namespace", value_name = "PREFIX=URI")]
+    xmp_namespaces: Vec<XmpNamespace>,
+    /// Custom XMP property to write from a front matter key, e.g.
+    /// `kaishi:permalink=permalink`. The prefix must be `dc`, `xmp`,
+    /// `pdf` or declared with --xmp-namespace.
+    #[arg(long = "xmp-property", value_name = "PREFIX:NAME=KEY")]
+    xmp_properties: Vec<XmpProperty>,
+    /// Write byte-identical PDFs for identical inputs: fix the document
+    /// ID and object numbers, and pin the dates missing from the front
+    /// matter to SOURCE_DATE_EPOCH, or the Unix epoch if it is not set.
+    /// SOURCE_DATE_EPOCH pins the dates even without this flag.
+    #[arg(long)]
+    reproducible: bool,
+    /// Regenerate every PDF, even those that are up to date.
+    #[arg(long)]
+    force: bool,
+}
+
+impl BuildArgs {
+    // Turns the command-line options into the build settings.
+    fn build_config(
+        &self,
+        source_args: &SourceArgs,
+    ) -> Result<BuildConfig, Box<dyn std::error::Error>> {
+        let mut config = BuildConfig::default();
+        config.template_options.list_separator = self.list_separator.clone();
+        config.template_options.missing_key_policy = self.missing_keys;
+        for extension in &self.disabled_extensions {
+            config.markdown_options.set_extension(extension, false)?;
+        }
+        config.layouts_directory = self.layouts_dir.clone();
+        config.theme = self.theme.clone();
+        config.themes_directory = self.themes_dir.clone();
+        config.page_setup = PageSetup {
+            paper_size: self.paper_size,
+            orientation: self.orientation,
+            margins: self.margins,
+        };
+        config.output_options.directory = self.output_dir.clone();
+        config.output_options.filename_template =
+            self.filename_template.clone();
+        config.output_options.source_root = source_args.source_root();
+        if let Some(jobs) = self.jobs {
+            config.jobs = jobs;
+        }
+        config.max_tabs = self.max_tabs;
+        config.force = self.force;
+        for entry in &self.doc_info_entries {
+            config.metadata_options.set_doc_info_entry(entry.clone());
+        }
+        config.metadata_options.source_date_epoch = source_date_epoch()?;
+        config.metadata_options.deterministic = self.reproducible;
+        for namespace in &self.xmp_namespaces {
+            config.metadata_options.add_xmp_namespace(namespace.clone());
+        }
+        for property in &self.xmp_properties {
+            config.metadata_options.set_xmp_property(property.clone())?;
+        }
+        Ok(config)
+    }
+}
+
+impl SourceArgs {
+    // Resolves the paths and patterns into the list of source files.
+    fn source_files(&self) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+        let (roots, include_patterns, exclude_patterns) =
+            self.discovery_arguments();
+
+        discover_source_files(&roots, &include_patterns, &exclude_patterns)
+    }
+
+    // The roots and patterns to discover source files with.
+    fn discovery_arguments(&self) -> (Vec<PathBuf>, Vec<&str>, Vec<&str>) {
+        let roots = if self.paths.is_empty() {
+            vec![PathBuf::from(DEFAULT_SOURCE_DIRECTORY)]
+        } else {
+            self.paths.clone()
+        };
+        let include_patterns: Vec<&str> =
+            self.include_patterns.iter().map(String::as_str).collect();
+        let exclude_patterns: Vec<&str> =
+            self.exclude_patterns.iter().map(String::as_str).collect();
+
+        (roots, include_patterns, exclude_patterns)
+    }
+
+    // The directory whose structure is mirrored in the output directory:
+    // the single directory given, or else the current directory.
+    fn source_root(&self) -> PathBuf {
+        match &self.paths[..] {
+            [] => PathBuf::from(DEFAULT_SOURCE_DIRECTORY),
+            [path] if path.is_dir() => path.clone(),
+            _ => PathBuf::from("."),
+        }
+    }
+}
 
 fn main() {
-  dotenv().expect(".env file not found");
-  let project_name = env::var("PROJECT_NAME").unwrap().to_string();
-
-  // Call the `run()` function from the pdf_ok module.
-  if let Err(err) = pdf_ok::run() {
-      eprintln!("Error running {}: {}", project_name, err);
-      std::process::exit(1);
-  }
+    // A .env file is optional when running from the command line
+    dotenv().ok();
+    let project_name = env::var("PROJECT_NAME")
+        .unwrap_or_else(|_| env!("CARGO_PKG_NAME").to_string());
+
+    let cli = Cli::parse();
+    // With no subcommand, build the default source files
+    let command = cli.command.unwrap_or_else(|| {
+        Cli::parse_from([env!("CARGO_PKG_NAME"), "build"]).command.unwrap()
+    });
+
+    if let Err(err) = run_command(command) {
+        eprintln!("Error running {}: {}", project_name, err);
+        std::process::exit(1);
+    }
+}
+
+// Runs a single subcommand, mapping it onto the library functions.
+fn run_command(command: Command) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        Command::Build { source_args, build_args } => report_results(
+            read_file_data(
+                source_args.source_files()?,
+                &build_args.build_config(&source_args)?,
+            ),
+        ),
+        Command::Check { source_args, build_args } => report_results(
+            check_file_data(
+                source_args.source_files()?,
+                &build_args.build_config(&source_args)?,
+            ),
+        ),
+        Command::Watch { source_args, build_args } => {
+            let (roots, include_patterns, exclude_patterns) =
+                source_args.discovery_arguments();
+            watch_source_files(
+                &roots,
+                &include_patterns,
+                &exclude_patterns,
+                &build_args.build_config(&source_args)?,
+            )
+        }
+        Command::Inspect { pdf } => inspect_pdf(pdf),
+    }
+}
+
+// Prints every failed document and fails if there were any, so a CI job
+// sees a non-zero exit code.
+fn report_results(
+    results: Vec<Result<PathBuf, Error>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let failed: Vec<&Error> =
+        results.iter().filter_map(|result| result.as_ref().err()).collect();
+    for error in &failed {
+        eprintln!("{} {}", "error".bright_red(), error);
+    }
+
+    println!(
+        "{} {} of {} documents succeeded",
+        "Summary:".cyan(),
+        results.len() - failed.len(),
+        results.len()
+    );
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("{} documents failed", failed.len()).into())
+    }
 }
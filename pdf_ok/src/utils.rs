@@ -1,14 +1,125 @@
 /// The `source_files` module contains functions for working with source files.
 mod sources_files;
-pub use sources_files::get_source_front_matter_files;
+pub use sources_files::{
+    discover_source_files, get_source_front_matter_files,
+    DEFAULT_INCLUDE_PATTERN, DEFAULT_SOURCE_DIRECTORY,
+};
+
+/// The `front_matter` module contains a function for splitting front matter from the document body.
+mod front_matter;
+pub use front_matter::{
+    parse_front_matter, split_front_matter, FrontMatterFormat,
+    FrontMatterSplit,
+};
+
+/// The `front_matter_value` module contains the format-independent front matter value type.
+mod front_matter_value;
+pub use front_matter_value::{FrontMatter, FrontMatterValue};
+
+/// The `placeholders` module contains functions for writing front matter values into Markdown.
+mod placeholders;
+pub use placeholders::{
+    format_value, merge_markdown_yaml, resolve_path, unresolved_placeholders,
+    MergedMarkdown, MissingKeyPolicy, TemplateError, TemplateOptions,
+};
+
+/// The `template_blocks` module contains the `{% if %}` and `{% for %}` block templating.
+mod template_blocks;
+pub use template_blocks::render_markdown_template;
+
+/// The `render_markdown` module contains the CommonMark/GFM Markdown to HTML conversion.
+mod render_markdown;
+pub use render_markdown::{render_markdown, MarkdownOptions};
+
+/// The `dates` module contains functions for reading and formatting front matter dates.
+mod dates;
+pub use dates::{
+    format_date, format_pdf_date, parse_date, parse_pdf_date,
+    parse_source_date_epoch, source_date_epoch,
+};
+
+/// The `layouts` module contains functions for wrapping rendered documents in HTML layouts.
+mod layouts;
+pub use layouts::{
+    apply_layout, select_layout, Layout, DEFAULT_LAYOUT,
+    DEFAULT_LAYOUTS_DIRECTORY,
+};
+
+/// The `themes` module contains functions for loading CSS themes and inlining them into documents.
+mod themes;
+pub use themes::{
+    inline_theme, load_theme, select_theme, Theme, DEFAULT_THEME,
+    DEFAULT_THEMES_DIRECTORY, NO_THEME,
+};
+
+/// The `page_setup` module contains the paper size, orientation and margins documents are printed with.
+mod page_setup;
+pub use page_setup::{
+    Length, LengthUnit, Margins, PageSetup, PaperOrientation, PaperSize,
+};
+
+/// The `viewer_preferences` module contains how viewers present the generated PDFs when they are opened.
+mod viewer_preferences;
+pub use viewer_preferences::{PageLayout, PageMode, ViewerPreferences, Zoom};
+
+/// The `output_paths` module contains functions for naming and placing the generated PDFs.
+mod output_paths;
+pub use output_paths::{
+    output_path, sanitize_file_name, OutputOptions, DEFAULT_FILENAME_TEMPLATE,
+    DEFAULT_OUTPUT_DIRECTORY,
+};
+
+/// The `doc_info` module contains the mapping of front matter keys onto the PDF document information dictionary.
+mod doc_info;
+pub use doc_info::{
+    default_doc_info_entries, normalize_info_key, DocInfoEntry, DocumentDates,
+    MetadataOptions, MissingMetadataPolicy, CREATION_DATE_KEYS,
+    MODIFICATION_DATE_KEYS, STANDARD_INFO_KEYS,
+};
+
+/// The `pdf_text` module contains the encoding of PDF text strings.
+mod pdf_text;
+pub use pdf_text::{decode_pdf_text, encode_pdf_text, pdf_text_string};
+
+/// The `pdf_metadata` module contains the editing of the document information dictionary of PDFs.
+mod pdf_metadata;
+pub use pdf_metadata::DocumentMetadata;
+
+/// The `xmp` module contains the XMP metadata packet written into each PDF.
+mod xmp;
+pub use xmp::{xmp_packet, XmpNamespace, XmpProperty};
+
+/// The `deterministic` module contains the normalisation that makes saved PDFs byte-identical for identical inputs.
+mod deterministic;
+pub use deterministic::{make_deterministic, normalize_object_numbers};
+
+/// The `build_cache` module contains the content-hash cache used to skip documents that are up to date.
+mod build_cache;
+pub use build_cache::{content_hash, BuildCache, BUILD_CACHE_FILE_NAME};
+
+/// The `build_config` module contains the settings shared by every document in a build.
+mod build_config;
+pub use build_config::BuildConfig;
+
+/// The `renderer` module contains the headless Chrome instance shared by the documents of a build.
+mod renderer;
+pub use renderer::{Renderer, RendererTab, DEFAULT_MAX_TABS};
 
 /// The `generate_pdf` module contains functions for generating PDF files.
 mod generate_pdf;
-pub use generate_pdf::generate_pdf;
+pub use generate_pdf::{generate_pdf, post_process_pdf, PDF_PRODUCER};
 
 /// The `read_file_data` module contains functions for reading files.
 mod read_file_data;
-pub use read_file_data::read_file_data;
+pub use read_file_data::{check_file_data, read_file_data};
+
+/// The `watch` module contains the watch mode that rebuilds PDFs when their sources change.
+mod watch;
+pub use watch::{watch_source_files, WATCH_DEBOUNCE};
+
+/// The `inspect_pdf` module contains a function for summarising existing PDF files.
+mod inspect_pdf;
+pub use inspect_pdf::inspect_pdf;
 
 /// The `extract_to_end_string` module contains a function to extract everything to the end of the string after selected delimiter.
 mod extract_to_end_string;
@@ -0,0 +1,128 @@
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::Error;
+
+/// The name of the build cache file, kept in the output directory.
+pub const BUILD_CACHE_FILE_NAME: &str = ".pdf_ok_cache.json";
+
+/// Records the content hash each PDF was last generated from, so
+/// documents whose inputs have not changed can be skipped.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+///
+/// use pdf_ok::utils::{content_hash, BuildCache};
+///
+/// let source = Path::new("docs/a.md");
+/// // Any existing file stands in for the generated PDF
+/// let output = Path::new("Cargo.toml");
+///
+/// let mut cache = BuildCache::default();
+/// let hash = content_hash(&["front matter".as_bytes(), "body".as_bytes()]);
+/// cache.record(source, &hash, output);
+///
+/// assert!(cache.is_up_to_date(source, &hash, output));
+/// assert!(!cache.is_up_to_date(source, "changed", output));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildCache {
+    // The cache entry of each source file, by path
+    entries: BTreeMap<PathBuf, CacheEntry>,
+}
+
+// What a source file was last built from and into.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct CacheEntry {
+    hash: String,
+    output: PathBuf,
+}
+
+impl BuildCache {
+    /// Reads the build cache from a file. A missing file gives an empty
+    /// cache, as does a file that cannot be read, which is reported as a
+    /// warning.
+    pub fn load<P: AsRef<Path>>(path: P) -> Self {
+        let path = path.as_ref();
+        let Ok(content) = fs::read_to_string(path) else {
+            return BuildCache::default();
+        };
+
+        serde_json::from_str(&content).unwrap_or_else(|error| {
+            println!(
+                "{} ignoring the build cache {}: {}",
+                "warning".bright_yellow(),
+                path.display(),
+                error
+            );
+            BuildCache::default()
+        })
+    }
+
+    /// Writes the build cache to a file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error::Io`] if the file cannot be written.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let path = path.as_ref();
+        let content = serde_json::to_string_pretty(self)
+            .expect("the build cache is always valid JSON");
+
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory).map_err(|source| Error::Io {
+                path: directory.to_path_buf(),
+                source,
+            })?;
+        }
+        fs::write(path, content)
+            .map_err(|source| Error::Io { path: path.to_path_buf(), source })
+    }
+
+    /// Returns whether the PDF of a source file was last generated from
+    /// the same content hash, to the same output path, and still exists.
+    pub fn is_up_to_date(
+        &self,
+        source: &Path,
+        hash: &str,
+        output: &Path,
+    ) -> bool {
+        self.entries.get(source).is_some_and(|entry| {
+            entry.hash == hash && entry.output == output && output.is_file()
+        })
+    }
+
+    /// Records that the PDF of a source file was generated from a
+    /// content hash.
+    pub fn record(&mut self, source: &Path, hash: &str, output: &Path) {
+        self.entries.insert(
+            source.to_path_buf(),
+            CacheEntry { hash: hash.to_string(), output: output.to_path_buf() },
+        );
+    }
+
+    /// Forgets a source file, so it is rebuilt next time.
+    pub fn remove(&mut self, source: &Path) {
+        self.entries.remove(source);
+    }
+}
+
+/// Returns the SHA-256 hash of a list of inputs as a hexadecimal string.
+///
+/// Each input is prefixed with its length, so moving bytes from one
+/// input to the next changes the hash.
+pub fn content_hash(parts: &[&[u8]]) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part);
+    }
+
+    format!("{:x}", hasher.finalize())
+}
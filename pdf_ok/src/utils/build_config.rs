@@ -0,0 +1,71 @@
+use std::path::PathBuf;
+use std::thread;
+
+use crate::utils::{
+    MarkdownOptions, MetadataOptions, OutputOptions, PageSetup, TemplateOptions, DEFAULT_LAYOUTS_DIRECTORY,
+    DEFAULT_MAX_TABS, DEFAULT_THEME, DEFAULT_THEMES_DIRECTORY,
+};
+
+/// Settings shared by every document in a build.
+///
+/// # Examples
+///
+/// ```
+/// use pdf_ok::utils::{BuildConfig, PaperSize};
+///
+/// let mut config = BuildConfig::default();
+/// config.template_options.list_separator = " / ".to_string();
+/// config.markdown_options.footnotes = false;
+/// config.layouts_directory = "templates".into();
+/// config.theme = "corporate".to_string();
+/// config.page_setup.paper_size = PaperSize::Letter;
+/// config.output_options.directory = "build/pdfs".into();
+/// config.jobs = 2;
+/// config.force = true;
+/// ```
+#[derive(Debug, Clone)]
+pub struct BuildConfig {
+    /// How front matter values are written into the Markdown content.
+    pub template_options: TemplateOptions,
+    /// Which Markdown extensions are used when converting to HTML.
+    pub markdown_options: MarkdownOptions,
+    /// The directory the HTML layouts are read from.
+    pub layouts_directory: PathBuf,
+    /// The theme used by documents without a `theme` front matter key.
+    pub theme: String,
+    /// The directory the CSS themes are read from.
+    pub themes_directory: PathBuf,
+    /// The paper size, orientation and margins, which documents can
+    /// override in their front matter.
+    pub page_setup: PageSetup,
+    /// Where the PDFs are written and how they are named.
+    pub output_options: OutputOptions,
+    /// The number of documents processed at the same time. Defaults to
+    /// the number of CPUs.
+    pub jobs: usize,
+    /// The number of Chrome tabs open at the same time.
+    pub max_tabs: usize,
+    /// The metadata written into each PDF.
+    pub metadata_options: MetadataOptions,
+    /// Regenerate every PDF, even those the build cache says are up to
+    /// date.
+    pub force: bool,
+}
+
+impl Default for BuildConfig {
+    fn default() -> Self {
+        BuildConfig {
+            template_options: TemplateOptions::default(),
+            markdown_options: MarkdownOptions::default(),
+            layouts_directory: PathBuf::from(DEFAULT_LAYOUTS_DIRECTORY),
+            theme: DEFAULT_THEME.to_string(),
+            themes_directory: PathBuf::from(DEFAULT_THEMES_DIRECTORY),
+            page_setup: PageSetup::default(),
+            output_options: OutputOptions::default(),
+            jobs: thread::available_parallelism().map_or(1, |jobs| jobs.get()),
+            max_tabs: DEFAULT_MAX_TABS,
+            metadata_options: MetadataOptions::default(),
+            force: false,
+        }
+    }
+}
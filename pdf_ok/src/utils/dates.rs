@@ -0,0 +1,247 @@
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use std::env;
+
+/// Parses a date written in one of the formats commonly found in front
+/// matter.
+///
+/// The supported formats are:
+///
+/// * RFC 2822, e.g. `Tue, 20 Feb 2024 15:15:15 GMT`
+/// * RFC 3339 / ISO 8601 date and time, e.g. `2024-02-20T15:15:15+01:00`
+/// * ISO 8601 date and time without an offset, e.g. `2024-02-20 15:15:15`
+/// * ISO 8601 date, e.g. `2023-07-05`
+/// * Long month name dates, e.g. `July 12, 2023` or `12 July 2023`
+///
+/// Dates and times without an offset are taken to be in UTC.
+///
+/// # Returns
+///
+/// The parsed date and time, or `None` if the text matches none of the
+/// formats.
+///
+/// # Examples
+///
+/// ```
+/// use pdf_ok::utils::parse_date;
+///
+/// let date = parse_date("July 12, 2023").unwrap();
+/// assert_eq!(date.to_rfc3339(), "2023-07-12T00:00:00+00:00");
+///
+/// let date = parse_date("Tue, 20 Feb 2024 15:15:15 GMT").unwrap();
+/// assert_eq!(date.to_rfc3339(), "2024-02-20T15:15:15+00:00");
+/// ```
+pub fn parse_date(text: &str) -> Option<DateTime<FixedOffset>> {
+    let text = text.trim();
+
+    if let Ok(date_time) = DateTime::parse_from_rfc2822(text) {
+        return Some(date_time);
+    }
+    if let Ok(date_time) = DateTime::parse_from_rfc3339(text) {
+        return Some(date_time);
+    }
+
+    const DATE_TIME_FORMATS: [&str; 4] = [
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M",
+    ];
+    for format in DATE_TIME_FORMATS {
+        if let Ok(date_time) = NaiveDateTime::parse_from_str(text, format) {
+            return Some(Utc.from_utc_datetime(&date_time).fixed_offset());
+        }
+    }
+
+    const DATE_FORMATS: [&str; 5] =
+        ["%Y-%m-%d", "%B %d, %Y", "%b %d, %Y", "%d %B %Y", "%d %b %Y"];
+    for format in DATE_FORMATS {
+        if let Ok(date) = NaiveDate::parse_from_str(text, format) {
+            let date_time = date.and_hms_opt(0, 0, 0)?;
+            return Some(Utc.from_utc_datetime(&date_time).fixed_offset());
+        }
+    }
+
+    None
+}
+
+/// Formats a date with a `strftime`-style format string, such as
+/// `"%d %B %Y"`.
+///
+/// # Errors
+///
+/// Returns a description of the problem if the format string contains
+/// an unknown specifier.
+///
+/// # Examples
+///
+/// ```
+/// use pdf_ok::utils::{format_date, parse_date};
+///
+/// let date = parse_date("2023-07-05").unwrap();
+/// assert_eq!(format_date(&date, "%d %B %Y").unwrap(), "05 July 2023");
+/// assert!(format_date(&date, "%Q").is_err());
+/// ```
+pub fn format_date(
+    date: &DateTime<FixedOffset>,
+    format: &str,
+) -> Result<String, String> {
+    let items: Vec<Item<'_>> = StrftimeItems::new(format).collect();
+    if items.iter().any(|item| matches!(item, Item::Error)) {
+        return Err(format!("invalid date format \"{}\"", format));
+    }
+
+    Ok(date.format_with_items(items.into_iter()).to_string())
+}
+
+/// Parses a date in the PDF date format, `D:YYYYMMDDHHmmSSOHH'mm'`, as
+/// written in the `CreationDate` and `ModDate` entries of a PDF. Every
+/// part after the year is optional; a missing offset means UTC.
+///
+/// # Examples
+///
+/// ```
+/// use pdf_ok::utils::parse_pdf_date;
+///
+/// let date = parse_pdf_date("D:20240220151515+01'00'").unwrap();
+/// assert_eq!(date.to_rfc3339(), "2024-02-20T15:15:15+01:00");
+///
+/// let date = parse_pdf_date("D:2023").unwrap();
+/// assert_eq!(date.to_rfc3339(), "2023-01-01T00:00:00+00:00");
+///
+/// assert_eq!(parse_pdf_date("D:20240220151515+99'00'"), None);
+/// assert_eq!(parse_pdf_date("D:20240220151515+99999999999'00'"), None);
+/// ```
+pub fn parse_pdf_date(text: &str) -> Option<DateTime<FixedOffset>> {
+    let text = text.trim();
+    let text = text.strip_prefix("D:").unwrap_or(text);
+    let digits_end = text
+        .find(|character: char| !character.is_ascii_digit())
+        .unwrap_or(text.len());
+    let (digits, offset) = text.split_at(digits_end);
+    if digits.len() < 4 || digits.len() % 2 != 0 || digits.len() > 14 {
+        return None;
+    }
+
+    // Each missing part takes its lowest value
+    let part = |start: usize, default: u32| {
+        digits
+            .get(start..start + 2)
+            .map_or(Some(default), |part| part.parse().ok())
+    };
+    let date = NaiveDate::from_ymd_opt(
+        digits[..4].parse().ok()?,
+        part(4, 1)?,
+        part(6, 1)?,
+    )?;
+    let date_time =
+        date.and_hms_opt(part(8, 0)?, part(10, 0)?, part(12, 0)?)?;
+
+    let offset_seconds = match offset.chars().next() {
+        None | Some('Z') => 0,
+        Some(sign @ ('+' | '-')) => {
+            let mut numbers = offset[1..]
+                .split('\'')
+                .filter(|number| !number.is_empty())
+                .map(str::parse::<u8>);
+            let hours = numbers.next()?.ok()?;
+            let minutes = numbers.next().unwrap_or(Ok(0)).ok()?;
+            // Out of range offsets are malformed, not just unusual
+            if hours > 23 || minutes > 59 {
+                return None;
+            }
+            let seconds = i32::from(hours) * 3600 + i32::from(minutes) * 60;
+            if sign == '-' {
+                -seconds
+            } else {
+                seconds
+            }
+        }
+        Some(_) => return None,
+    };
+
+    FixedOffset::east_opt(offset_seconds)?
+        .from_local_datetime(&date_time)
+        .single()
+}
+
+/// Formats a date in the PDF date format, `D:YYYYMMDDHHmmSSOHH'mm'`, for
+/// the `CreationDate` and `ModDate` entries of a PDF.
+///
+/// # Examples
+///
+/// ```
+/// use pdf_ok::utils::{format_pdf_date, parse_date};
+///
+/// let date = parse_date("2024-02-20T15:15:15-05:30").unwrap();
+/// assert_eq!(format_pdf_date(&date), "D:20240220151515-05'30'");
+///
+/// let date = parse_date("July 12, 2023").unwrap();
+/// assert_eq!(format_pdf_date(&date), "D:20230712000000+00'00'");
+/// ```
+pub fn format_pdf_date(date: &DateTime<FixedOffset>) -> String {
+    let offset_minutes = date.offset().local_minus_utc() / 60;
+    let sign = if offset_minutes < 0 { '-' } else { '+' };
+
+    format!(
+        "{}{}{:02}'{:02}'",
+        date.format("D:%Y%m%d%H%M%S"),
+        sign,
+        offset_minutes.abs() / 60,
+        offset_minutes.abs() % 60
+    )
+}
+
+/// Reads the time reproducible builds pin their dates to from the
+/// `SOURCE_DATE_EPOCH` environment variable, a number of seconds since
+/// the Unix epoch.
+///
+/// # Returns
+///
+/// The time in UTC, or `None` if the variable is not set.
+///
+/// # Errors
+///
+/// Returns a description of the problem if the variable is set but is
+/// not a valid number of seconds.
+pub fn source_date_epoch() -> Result<Option<DateTime<FixedOffset>>, String> {
+    match env::var("SOURCE_DATE_EPOCH") {
+        Ok(seconds) => parse_source_date_epoch(&seconds).map(Some),
+        Err(env::VarError::NotPresent) => Ok(None),
+        Err(error) => Err(format!("invalid SOURCE_DATE_EPOCH: {}", error)),
+    }
+}
+
+/// Parses a `SOURCE_DATE_EPOCH` value, a number of seconds since the
+/// Unix epoch, as a time in UTC.
+///
+/// # Errors
+///
+/// Returns a description of the problem if the value is not a valid
+/// number of seconds.
+///
+/// # Examples
+///
+/// ```
+/// use pdf_ok::utils::parse_source_date_epoch;
+///
+/// let date = parse_source_date_epoch("1708442115").unwrap();
+/// assert_eq!(date.to_rfc3339(), "2024-02-20T15:15:15+00:00");
+/// assert!(parse_source_date_epoch("yesterday").is_err());
+/// ```
+pub fn parse_source_date_epoch(
+    seconds: &str,
+) -> Result<DateTime<FixedOffset>, String> {
+    seconds
+        .trim()
+        .parse::<i64>()
+        .ok()
+        .and_then(|seconds| DateTime::from_timestamp(seconds, 0))
+        .map(|date| date.fixed_offset())
+        .ok_or_else(|| {
+            format!(
+                "invalid SOURCE_DATE_EPOCH \"{}\" (expected a number of seconds)",
+                seconds.trim()
+            )
+        })
+}
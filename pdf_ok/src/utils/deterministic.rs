@@ -0,0 +1,142 @@
+use lopdf::{Document, Object as LopdfObject, ObjectId, StringFormat};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, VecDeque};
+
+/// Prepares a document so that saving it gives the same bytes whenever
+/// its content is the same.
+///
+/// The objects are renumbered with [`normalize_object_numbers`], and the
+/// trailer's `/ID`, which Chrome fills with random bytes, is replaced by
+/// a hash of the rest of the document. The dates and producer are not
+/// touched; they are pinned by
+/// [`MetadataOptions::dates`](crate::utils::MetadataOptions::dates) and
+/// [`post_process_pdf`](crate::utils::post_process_pdf).
+///
+/// # Examples
+///
+/// ```
+/// use lopdf::{dictionary, Document, Object};
+///
+/// use pdf_ok::utils::make_deterministic;
+///
+/// let mut doc = Document::with_version("1.7");
+/// let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog" });
+/// doc.trailer.set("Root", catalog_id);
+/// doc.trailer.set(
+///     "ID",
+///     vec![Object::string_literal("random"), Object::string_literal("random")],
+/// );
+///
+/// make_deterministic(&mut doc);
+/// let id = doc.trailer.get(b"ID").unwrap().as_array().unwrap();
+/// assert_eq!(id.len(), 2);
+/// assert_eq!(id[0], id[1]);
+/// assert_ne!(id[0], Object::string_literal("random"));
+/// ```
+pub fn make_deterministic(doc: &mut Document) {
+    normalize_object_numbers(doc);
+
+    // Hash the document without an /ID, then use the hash as its /ID
+    doc.trailer.remove(b"ID");
+    let mut bytes = Vec::new();
+    let id: Vec<u8> = match doc.save_to(&mut bytes) {
+        Ok(()) => Sha256::digest(&bytes)[..16].to_vec(),
+        // Writing to memory does not fail, but leave the /ID out if it did
+        Err(_) => return,
+    };
+    doc.trailer.set(
+        "ID",
+        vec![
+            LopdfObject::String(id.clone(), StringFormat::Hexadecimal),
+            LopdfObject::String(id, StringFormat::Hexadecimal),
+        ],
+    );
+}
+
+/// Renumbers the objects of a document in the order they are reached
+/// from the trailer, starting at 1 with generation 0, and drops the
+/// objects nothing refers to.
+///
+/// The numbers then depend only on the structure of the document, not
+/// on the order the objects were written or added in. References to
+/// objects that do not exist become `null`.
+pub fn normalize_object_numbers(doc: &mut Document) {
+    // Number the objects breadth first, visiting the trailer's entries
+    // in key order
+    let mut trailer_entries: Vec<_> = doc.trailer.iter().collect();
+    trailer_entries.sort_by_key(|(a, _)| *a);
+    let mut queue = VecDeque::new();
+    for (_, value) in trailer_entries {
+        collect_references(value, &mut queue);
+    }
+
+    let mut new_ids: BTreeMap<ObjectId, ObjectId> = BTreeMap::new();
+    while let Some(id) = queue.pop_front() {
+        if new_ids.contains_key(&id) {
+            continue;
+        }
+        if let Some(object) = doc.objects.get(&id) {
+            new_ids.insert(id, (new_ids.len() as u32 + 1, 0));
+            collect_references(object, &mut queue);
+        }
+    }
+
+    let mut objects = BTreeMap::new();
+    for (old_id, new_id) in &new_ids {
+        if let Some(mut object) = doc.objects.remove(old_id) {
+            replace_references(&mut object, &new_ids);
+            objects.insert(*new_id, object);
+        }
+    }
+    for (_, value) in doc.trailer.iter_mut() {
+        replace_references(value, &new_ids);
+    }
+
+    doc.max_id = objects.len() as u32;
+    doc.objects = objects;
+}
+
+// Adds the objects an object refers to to the queue, in the order they
+// appear.
+fn collect_references(object: &LopdfObject, queue: &mut VecDeque<ObjectId>) {
+    match object {
+        LopdfObject::Reference(id) => queue.push_back(*id),
+        LopdfObject::Array(items) => {
+            items.iter().for_each(|item| collect_references(item, queue));
+        }
+        LopdfObject::Dictionary(dictionary) => dictionary
+            .iter()
+            .for_each(|(_, value)| collect_references(value, queue)),
+        LopdfObject::Stream(stream) => stream
+            .dict
+            .iter()
+            .for_each(|(_, value)| collect_references(value, queue)),
+        _ => {}
+    }
+}
+
+// Points the references in an object at the new object numbers.
+fn replace_references(
+    object: &mut LopdfObject,
+    new_ids: &BTreeMap<ObjectId, ObjectId>,
+) {
+    match object {
+        LopdfObject::Reference(id) => {
+            *object = match new_ids.get(id) {
+                Some(new_id) => LopdfObject::Reference(*new_id),
+                None => LopdfObject::Null,
+            };
+        }
+        LopdfObject::Array(items) => items
+            .iter_mut()
+            .for_each(|item| replace_references(item, new_ids)),
+        LopdfObject::Dictionary(dictionary) => dictionary
+            .iter_mut()
+            .for_each(|(_, value)| replace_references(value, new_ids)),
+        LopdfObject::Stream(stream) => stream
+            .dict
+            .iter_mut()
+            .for_each(|(_, value)| replace_references(value, new_ids)),
+        _ => {}
+    }
+}
@@ -0,0 +1,485 @@
+use chrono::{DateTime, FixedOffset};
+use colored::Colorize;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::utils::{
+    format_value, parse_date, resolve_path, FrontMatter, TemplateOptions,
+    XmpNamespace, XmpProperty,
+};
+use crate::Error;
+
+/// The keys of the document information dictionary defined by the PDF
+/// specification, in their conventional spelling.
+pub const STANDARD_INFO_KEYS: [&str; 9] = [
+    "Title",
+    "Author",
+    "Subject",
+    "Keywords",
+    "Creator",
+    "Producer",
+    "CreationDate",
+    "ModDate",
+    "Trapped",
+];
+
+/// The front matter keys the creation date of a PDF is read from, in
+/// order of preference.
+pub const CREATION_DATE_KEYS: [&str; 2] = ["date", "pub_date"];
+
+/// The front matter keys the modification date of a PDF is read from,
+/// in order of preference.
+pub const MODIFICATION_DATE_KEYS: [&str; 1] = ["last_build_date"];
+
+/// What to do when the front matter key of a metadata field is missing,
+/// null or empty.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum MissingMetadataPolicy {
+    /// Leave the field out, keeping any value Chrome wrote.
+    #[default]
+    Skip,
+    /// Use this value instead.
+    Default(String),
+    /// Fail the document with [`Error::MissingMetadataKey`].
+    Fail,
+}
+
+impl FromStr for MissingMetadataPolicy {
+    type Err = String;
+
+    /// Reads `skip`, `fail` or `default=VALUE`.
+    fn from_str(policy: &str) -> Result<Self, Self::Err> {
+        match policy.split_once('=') {
+            Some((name, value)) if name.trim().eq_ignore_ascii_case("default") => {
+                Ok(MissingMetadataPolicy::Default(value.to_string()))
+            }
+            _ => match policy.trim().to_lowercase().as_str() {
+                "skip" => Ok(MissingMetadataPolicy::Skip),
+                "fail" => Ok(MissingMetadataPolicy::Fail),
+                _ => Err(format!(
+                    "unknown missing metadata policy \"{}\" (expected skip, fail or default=VALUE)",
+                    policy
+                )),
+            },
+        }
+    }
+}
+
+/// Maps a front matter key onto an entry of the PDF document information
+/// (Info) dictionary.
+///
+/// # Examples
+///
+/// ```
+/// use pdf_ok::utils::{DocInfoEntry, MissingMetadataPolicy};
+///
+/// let entry = DocInfoEntry::new("AuthoR", "author").unwrap();
+/// assert_eq!(entry.doc_info_entry, "Author");
+/// assert_eq!(entry.yaml_entry, "author");
+/// assert_eq!(entry.missing, MissingMetadataPolicy::Skip);
+/// assert!(DocInfoEntry::new(" ", "author").is_err());
+///
+/// let entry: DocInfoEntry = "Subject=summary:default=No summary".parse().unwrap();
+/// assert_eq!(
+///     entry.missing,
+///     MissingMetadataPolicy::Default("No summary".to_string())
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocInfoEntry {
+    /// The Info dictionary key, e.g. `Author`.
+    pub doc_info_entry: String,
+    /// The front matter key its value is read from, e.g. `author` or
+    /// `author.name`.
+    pub yaml_entry: String,
+    /// What to do if the front matter key is missing.
+    pub missing: MissingMetadataPolicy,
+}
+
+impl DocInfoEntry {
+    /// Creates a mapping that is skipped when the front matter key is
+    /// missing, normalising the Info key with [`normalize_info_key`].
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the problem if the Info key or the
+    /// front matter key is empty.
+    pub fn new(
+        doc_info_entry: &str,
+        yaml_entry: &str,
+    ) -> Result<Self, String> {
+        let doc_info_entry = normalize_info_key(doc_info_entry);
+        let yaml_entry = yaml_entry.trim();
+        if doc_info_entry.is_empty() || yaml_entry.is_empty() {
+            return Err(format!(
+                "invalid Info mapping \"{}={}\" (expected a non-empty Info key and front matter key)",
+                doc_info_entry, yaml_entry
+            ));
+        }
+
+        Ok(DocInfoEntry {
+            doc_info_entry,
+            yaml_entry: yaml_entry.to_string(),
+            missing: MissingMetadataPolicy::Skip,
+        })
+    }
+
+    /// Sets what to do when the front matter key is missing.
+    pub fn with_missing(mut self, missing: MissingMetadataPolicy) -> Self {
+        self.missing = missing;
+        self
+    }
+
+    /// Returns the value of the field for a document: the front matter
+    /// value, or what the missing policy gives if it is missing, null
+    /// or empty. Lists are joined with `, `.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error::MissingMetadataKey`] naming the file and key
+    /// if the value is missing and the policy is
+    /// [`MissingMetadataPolicy::Fail`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::Path;
+    ///
+    /// use pdf_ok::utils::{
+    ///     parse_front_matter, DocInfoEntry, FrontMatterFormat,
+    ///     MissingMetadataPolicy,
+    /// };
+    ///
+    /// let front_matter =
+    ///     parse_front_matter("tags: [rust, pdf]", FrontMatterFormat::Yaml).unwrap();
+    /// let path = Path::new("example.md");
+    ///
+    /// let keywords = DocInfoEntry::new("Keywords", "tags").unwrap();
+    /// assert_eq!(
+    ///     keywords.value(&front_matter, path).unwrap().as_deref(),
+    ///     Some("rust, pdf")
+    /// );
+    ///
+    /// let creator = DocInfoEntry::new("Creator", "generator")
+    ///     .unwrap()
+    ///     .with_missing(MissingMetadataPolicy::Fail);
+    /// let error = creator.value(&front_matter, path).unwrap_err();
+    /// assert_eq!(error.to_string(), "example.md: missing front matter key `generator`");
+    /// ```
+    pub fn value(
+        &self,
+        front_matter: &FrontMatter,
+        path: &Path,
+    ) -> Result<Option<String>, Error> {
+        let value = resolve_path(front_matter, &self.yaml_entry)
+            .map(|value| format_value(value, &TemplateOptions::default()))
+            .filter(|value| !value.trim().is_empty());
+
+        match (value, &self.missing) {
+            (Some(value), _) => Ok(Some(value)),
+            (None, MissingMetadataPolicy::Skip) => Ok(None),
+            (None, MissingMetadataPolicy::Default(default)) => {
+                Ok(Some(default.clone()))
+            }
+            (None, MissingMetadataPolicy::Fail) => {
+                Err(Error::MissingMetadataKey {
+                    path: path.to_path_buf(),
+                    key: self.yaml_entry.clone(),
+                })
+            }
+        }
+    }
+}
+
+impl FromStr for DocInfoEntry {
+    type Err = String;
+
+    /// Reads a mapping written `InfoKey=front_matter_key`, optionally
+    /// followed by `:` and a [`MissingMetadataPolicy`], e.g.
+    /// `Subject=description`, `Creator=generator:fail` or
+    /// `Title=title:default=Untitled`.
+    fn from_str(mapping: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "invalid Info mapping \"{}\" (expected InfoKey=front_matter_key[:policy])",
+                mapping
+            )
+        };
+        let (doc_info_entry, rest) = mapping.split_once('=').ok_or_else(invalid)?;
+        let (yaml_entry, missing) = match rest.split_once(':') {
+            Some((yaml_entry, policy)) => (yaml_entry, policy.parse()?),
+            None => (rest, MissingMetadataPolicy::Skip),
+        };
+
+        Ok(DocInfoEntry::new(doc_info_entry, yaml_entry)
+            .map_err(|_| invalid())?
+            .with_missing(missing))
+    }
+}
+
+/// Settings for the metadata written into each PDF.
+///
+/// # Examples
+///
+/// ```
+/// use pdf_ok::utils::{DocInfoEntry, MetadataOptions};
+///
+/// let mut options = MetadataOptions::default();
+/// options.set_doc_info_entry(DocInfoEntry::new("subject", "summary").unwrap());
+/// options.set_doc_info_entry(DocInfoEntry::new("Company", "organisation").unwrap());
+///
+/// let subject = options
+///     .doc_info_entries
+///     .iter()
+///     .find(|entry| entry.doc_info_entry == "Subject")
+///     .unwrap();
+/// assert_eq!(subject.yaml_entry, "summary");
+///
+/// options.add_xmp_namespace("kaishi=https://kaishi.example/ns/1.0/".parse().unwrap());
+/// options
+///     .set_xmp_property("kaishi:permalink=permalink".parse().unwrap())
+///     .unwrap();
+/// assert!(options
+///     .set_xmp_property("other:permalink=permalink".parse().unwrap())
+///     .is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetadataOptions {
+    /// The Info dictionary entries written from the front matter, in
+    /// order.
+    pub doc_info_entries: Vec<DocInfoEntry>,
+    /// The namespaces of the custom XMP properties.
+    pub xmp_namespaces: Vec<XmpNamespace>,
+    /// The custom XMP properties written from the front matter, in
+    /// addition to those derived from the Info dictionary.
+    pub xmp_properties: Vec<XmpProperty>,
+    /// The time dates missing from the front matter are pinned to for
+    /// reproducible builds, usually from
+    /// [`source_date_epoch`](crate::utils::source_date_epoch). Without
+    /// it they are left as Chrome wrote them, the time of printing,
+    /// unless [`deterministic`](MetadataOptions::deterministic) is set.
+    pub source_date_epoch: Option<DateTime<FixedOffset>>,
+    /// Whether identical inputs give byte-identical PDFs: dates missing
+    /// from the front matter fall back to the Unix epoch when there is no
+    /// `source_date_epoch`, and the `/ID` and object numbers are fixed
+    /// with [`make_deterministic`](crate::utils::make_deterministic).
+    pub deterministic: bool,
+}
+
+/// The creation and modification dates written into a PDF. `None` keeps
+/// the date Chrome wrote.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DocumentDates {
+    /// The `CreationDate` of the PDF.
+    pub creation: Option<DateTime<FixedOffset>>,
+    /// The `ModDate` of the PDF.
+    pub modification: Option<DateTime<FixedOffset>>,
+}
+
+impl Default for MetadataOptions {
+    fn default() -> Self {
+        MetadataOptions {
+            doc_info_entries: default_doc_info_entries(),
+            xmp_namespaces: Vec::new(),
+            xmp_properties: Vec::new(),
+            source_date_epoch: None,
+            deterministic: false,
+        }
+    }
+}
+
+impl MetadataOptions {
+    /// Adds a mapping, replacing any existing mapping for the same Info
+    /// key.
+    pub fn set_doc_info_entry(&mut self, entry: DocInfoEntry) {
+        match self
+            .doc_info_entries
+            .iter_mut()
+            .find(|existing| existing.doc_info_entry == entry.doc_info_entry)
+        {
+            Some(existing) => *existing = entry,
+            None => self.doc_info_entries.push(entry),
+        }
+    }
+
+    /// Removes the mapping for an Info key, if there is one.
+    pub fn remove_doc_info_entry(&mut self, doc_info_entry: &str) {
+        let doc_info_entry = normalize_info_key(doc_info_entry);
+        self.doc_info_entries
+            .retain(|entry| entry.doc_info_entry != doc_info_entry);
+    }
+
+    /// Returns the dates of a document: the creation date from the first
+    /// of [`CREATION_DATE_KEYS`] in the front matter, and the
+    /// modification date from the first of [`MODIFICATION_DATE_KEYS`].
+    /// A date missing from the front matter is the
+    /// [`source_date_epoch`](MetadataOptions::source_date_epoch), if set,
+    /// or else the Unix epoch in
+    /// [`deterministic`](MetadataOptions::deterministic) mode.
+    ///
+    /// A value that is not a date [`parse_date`] understands is reported
+    /// as a warning naming the file and key, and treated as missing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::Path;
+    ///
+    /// use pdf_ok::utils::{
+    ///     parse_front_matter, parse_source_date_epoch, FrontMatterFormat,
+    ///     MetadataOptions,
+    /// };
+    ///
+    /// let front_matter =
+    ///     parse_front_matter("pub_date: July 12, 2023", FrontMatterFormat::Yaml)
+    ///         .unwrap();
+    /// let options = MetadataOptions {
+    ///     source_date_epoch: Some(parse_source_date_epoch("1708442115").unwrap()),
+    ///     ..MetadataOptions::default()
+    /// };
+    ///
+    /// let dates = options.dates(&front_matter, Path::new("example.md"));
+    /// assert_eq!(dates.creation.unwrap().to_rfc3339(), "2023-07-12T00:00:00+00:00");
+    /// assert_eq!(dates.modification.unwrap().to_rfc3339(), "2024-02-20T15:15:15+00:00");
+    ///
+    /// let front_matter =
+    ///     parse_front_matter("date: someday", FrontMatterFormat::Yaml).unwrap();
+    /// let dates = MetadataOptions::default().dates(&front_matter, Path::new("example.md"));
+    /// assert_eq!(dates.creation, None);
+    /// ```
+    pub fn dates(
+        &self,
+        front_matter: &FrontMatter,
+        path: &Path,
+    ) -> DocumentDates {
+        let date = |keys: &[&str]| {
+            for key in keys {
+                let Some(value) = resolve_path(front_matter, key)
+                    .map(|value| format_value(value, &TemplateOptions::default()))
+                    .filter(|value| !value.trim().is_empty())
+                else {
+                    continue;
+                };
+                match parse_date(&value) {
+                    Some(date) => return Some(date),
+                    None => println!(
+                        "{} {}: `{}` is not a recognised date: \"{}\"",
+                        "warning".bright_yellow(),
+                        path.display(),
+                        key,
+                        value
+                    ),
+                }
+            }
+            self.source_date_epoch.or_else(|| {
+                self.deterministic.then(|| DateTime::UNIX_EPOCH.fixed_offset())
+            })
+        };
+
+        DocumentDates {
+            creation: date(&CREATION_DATE_KEYS),
+            modification: date(&MODIFICATION_DATE_KEYS),
+        }
+    }
+
+    /// Declares a namespace for custom XMP properties, replacing any
+    /// namespace with the same prefix.
+    pub fn add_xmp_namespace(&mut self, namespace: XmpNamespace) {
+        self.xmp_namespaces.retain(|existing| existing.prefix != namespace.prefix);
+        self.xmp_namespaces.push(namespace);
+    }
+
+    /// Adds a custom XMP property, replacing any property with the same
+    /// qualified name.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the problem if the prefix of the
+    /// property is neither built in nor declared with
+    /// [`add_xmp_namespace`](MetadataOptions::add_xmp_namespace).
+    pub fn set_xmp_property(
+        &mut self,
+        property: XmpProperty,
+    ) -> Result<(), String> {
+        let declared = ["dc", "xmp", "pdf"].contains(&property.prefix.as_str())
+            || self
+                .xmp_namespaces
+                .iter()
+                .any(|namespace| namespace.prefix == property.prefix);
+        if !declared {
+            return Err(format!(
+                "the XMP namespace prefix `{}` is not declared",
+                property.prefix
+            ));
+        }
+
+        self.xmp_properties
+            .retain(|existing| existing.qualified_name() != property.qualified_name());
+        self.xmp_properties.push(property);
+        Ok(())
+    }
+}
+
+/// Returns the Info dictionary mappings used when none are configured:
+/// `Title`, `Author`, `Subject` (from `description`), `Keywords`,
+/// `Creator` (from `generator`), `Language`, `Permalink` and
+/// `Site_components`, each read from the front matter key of the same
+/// name in lower case unless noted. All of them are skipped when the key
+/// is missing.
+pub fn default_doc_info_entries() -> Vec<DocInfoEntry> {
+    [
+        ("Title", "title"),
+        ("Author", "author"),
+        ("Subject", "description"),
+        ("Keywords", "keywords"),
+        ("Creator", "generator"),
+        ("Language", "language"),
+        ("Permalink", "permalink"),
+        ("Site_components", "site_components"),
+    ]
+    .into_iter()
+    .map(|(doc_info_entry, yaml_entry)| {
+        DocInfoEntry::new(doc_info_entry, yaml_entry)
+            .expect("the default Info mappings are valid")
+    })
+    .collect()
+}
+
+/// Normalises an Info dictionary key to the PDF conventions.
+///
+/// The standard keys are matched regardless of case and spelled as in
+/// the PDF specification (`AuthoR` becomes `Author`, `moddate` becomes
+/// `ModDate`). Other keys get an upper case first letter, and characters
+/// that cannot appear in a PDF name, such as spaces, become `_`.
+///
+/// # Examples
+///
+/// ```
+/// use pdf_ok::utils::normalize_info_key;
+///
+/// assert_eq!(normalize_info_key("AuthoR"), "Author");
+/// assert_eq!(normalize_info_key("creationdate"), "CreationDate");
+/// assert_eq!(normalize_info_key("site components"), "Site_components");
+/// ```
+pub fn normalize_info_key(key: &str) -> String {
+    let key = key.trim();
+    if let Some(standard) = STANDARD_INFO_KEYS
+        .iter()
+        .find(|standard| standard.eq_ignore_ascii_case(key))
+    {
+        return standard.to_string();
+    }
+
+    let mut characters = key.chars().map(|character| match character {
+        '(' | ')' | '<' | '>' | '[' | ']' | '{' | '}' | '/' | '%' | '#' => '_',
+        _ if !character.is_ascii_graphic() => '_',
+        _ => character,
+    });
+    match characters.next() {
+        Some(first) => {
+            first.to_ascii_uppercase().to_string()
+                + &characters.collect::<String>()
+        }
+        None => String::new(),
+    }
+}
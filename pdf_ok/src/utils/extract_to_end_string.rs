@@ -13,7 +13,7 @@
 /// # Example
 ///
 /// ```
-/// use your_crate_name::extract_to_end_string;
+/// use pdf_ok::utils::extract_to_end_string;
 ///
 /// let input = "my_string_to_test/path/to/filename";
 /// let delimiter_to_find = '/';
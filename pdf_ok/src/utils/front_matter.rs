@@ -0,0 +1,287 @@
+use crate::utils::{FrontMatter, FrontMatterValue};
+
+/// The byte order mark some editors write at the start of UTF-8 files.
+const UTF8_BOM: &str = "\u{feff}";
+
+/// The line that opens and closes YAML front matter.
+const YAML_FENCE: &str = "---";
+
+/// The alternative YAML closing fence (the YAML end-of-document marker).
+const YAML_END_FENCE: &str = "...";
+
+/// The line that opens and closes TOML front matter.
+const TOML_FENCE: &str = "+++";
+
+/// The formats front matter can be written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrontMatterFormat {
+    /// YAML between `---` fences.
+    Yaml,
+    /// TOML between `+++` fences, as used by Hugo.
+    Toml,
+    /// A JSON object at the very start of the file.
+    Json,
+}
+
+/// A source file split into its front matter and body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrontMatterSplit<'a> {
+    /// The text between the fences, without the fence lines themselves,
+    /// or `None` if the file has no front matter. JSON front matter
+    /// includes its enclosing braces.
+    pub front_matter: Option<&'a str>,
+    /// The format of the front matter. Files without front matter report
+    /// [`FrontMatterFormat::Yaml`].
+    pub format: FrontMatterFormat,
+    /// The rest of the file, exactly as it appears in the source.
+    pub body: &'a str,
+    /// The byte offset in the original content where the body starts.
+    pub body_offset: usize,
+}
+
+/// Splits the content of a source file into its front matter and body.
+///
+/// Front matter is only recognised at the very start of the file (after
+/// an optional UTF-8 byte order mark):
+///
+/// * YAML opens with a `---` line and runs until the next line that is
+///   `---` or `...`.
+/// * TOML opens with a `+++` line and runs until the next `+++` line.
+/// * JSON is an object whose opening `{` starts the first line and is
+///   followed, after any whitespace, by a `"` key. It runs until the
+///   matching `}`, which must end its line. A body that starts with a
+///   `{{placeholder}}` or `{% block %}` is not mistaken for JSON.
+///
+/// Any later fence lines, such as Markdown horizontal rules, are part of
+/// the body. Both `\n` and `\r\n` line endings are accepted. If the front
+/// matter is never closed, the file is treated as having no front
+/// matter.
+///
+/// The body is a slice of the original content, so it is kept
+/// byte-for-byte intact.
+///
+/// # Arguments
+///
+/// * `content` - The full text of the source file.
+///
+/// # Returns
+///
+/// A [`FrontMatterSplit`] with the front matter (if any), the body and
+/// the byte offset where the body starts.
+///
+/// # Examples
+///
+/// ```
+/// use pdf_ok::utils::split_front_matter;
+///
+/// let content = "---\r\ntitle: Example\r\n...\r\nIntro\r\n\r\n---\r\n\r\nMore\r\n";
+/// let split = split_front_matter(content);
+/// assert_eq!(split.front_matter, Some("title: Example\r\n"));
+/// assert_eq!(split.body, "Intro\r\n\r\n---\r\n\r\nMore\r\n");
+/// assert_eq!(&content[split.body_offset..], split.body);
+///
+/// let content = "{\n  \"title\": \"Example\"\n}\nBody\n";
+/// let split = split_front_matter(content);
+/// assert_eq!(split.front_matter, Some("{\n  \"title\": \"Example\"\n}"));
+/// assert_eq!(split.body, "Body\n");
+///
+/// let split = split_front_matter("{{title}}\n\nBody\n");
+/// assert_eq!(split.front_matter, None);
+/// assert_eq!(split.body, "{{title}}\n\nBody\n");
+///
+/// let split = split_front_matter("{% if draft %}\nDraft\n{% endif %}\n");
+/// assert_eq!(split.front_matter, None);
+///
+/// let split = split_front_matter("\u{feff}# No front matter\n---\n");
+/// assert_eq!(split.front_matter, None);
+/// assert_eq!(split.body, "# No front matter\n---\n");
+/// assert_eq!(split.body_offset, 3);
+/// ```
+pub fn split_front_matter(content: &str) -> FrontMatterSplit<'_> {
+    let start = if content.starts_with(UTF8_BOM) { UTF8_BOM.len() } else { 0 };
+    let no_front_matter = FrontMatterSplit {
+        front_matter: None,
+        format: FrontMatterFormat::Yaml,
+        body: &content[start..],
+        body_offset: start,
+    };
+
+    if starts_with_json_object(&content[start..]) {
+        return split_json_front_matter(content, start).unwrap_or(no_front_matter);
+    }
+
+    let mut lines = Lines { content, position: start };
+    let (format, closing_fences): (FrontMatterFormat, &[&str]) =
+        match lines.next() {
+            Some((line, _)) if is_fence(line, YAML_FENCE) => {
+                (FrontMatterFormat::Yaml, &[YAML_FENCE, YAML_END_FENCE])
+            }
+            Some((line, _)) if is_fence(line, TOML_FENCE) => {
+                (FrontMatterFormat::Toml, &[TOML_FENCE])
+            }
+            _ => return no_front_matter,
+        };
+
+    let front_matter_start = lines.position;
+    while let Some((line, line_start)) = lines.next() {
+        if closing_fences.iter().any(|fence| is_fence(line, fence)) {
+            return FrontMatterSplit {
+                front_matter: Some(&content[front_matter_start..line_start]),
+                format,
+                body: &content[lines.position..],
+                body_offset: lines.position,
+            };
+        }
+    }
+
+    // The opening fence was never closed
+    no_front_matter
+}
+
+/// Parses front matter text in the given format into [`FrontMatter`].
+///
+/// Empty YAML or TOML front matter gives an empty set of values.
+///
+/// # Arguments
+///
+/// * `front_matter` - The front matter text, as returned in
+///   [`FrontMatterSplit::front_matter`].
+/// * `format` - The format the text is written in.
+///
+/// # Errors
+///
+/// Returns a description of the problem if the text is not valid in its
+/// format, or if its top level is not a set of keys and values.
+///
+/// # Examples
+///
+/// ```
+/// use pdf_ok::utils::{
+///     parse_front_matter, split_front_matter, FrontMatterValue,
+/// };
+///
+/// let split = split_front_matter("+++\ntitle = \"Example\"\ndraft = false\n+++\nBody\n");
+/// let front_matter =
+///     parse_front_matter(split.front_matter.unwrap(), split.format).unwrap();
+/// assert_eq!(front_matter["title"], FrontMatterValue::String("Example".into()));
+/// assert_eq!(front_matter["draft"], FrontMatterValue::Bool(false));
+/// ```
+pub fn parse_front_matter(
+    front_matter: &str,
+    format: FrontMatterFormat,
+) -> Result<FrontMatter, String> {
+    let value: FrontMatterValue = match format {
+        FrontMatterFormat::Yaml => {
+            serde_yaml::from_str::<serde_yaml::Value>(front_matter)
+                .map_err(|error| error.to_string())?
+                .into()
+        }
+        FrontMatterFormat::Toml => toml::from_str::<toml::Value>(front_matter)
+            .map_err(|error| error.to_string())?
+            .into(),
+        FrontMatterFormat::Json => {
+            serde_json::from_str::<serde_json::Value>(front_matter)
+                .map_err(|error| error.to_string())?
+                .into()
+        }
+    };
+
+    match value {
+        FrontMatterValue::Mapping(mapping) => Ok(mapping),
+        // Empty front matter parses as null
+        FrontMatterValue::Null => Ok(FrontMatter::new()),
+        _ => Err("expected a mapping with string keys".to_string()),
+    }
+}
+
+// Whether text starts with what looks like a JSON object: a `{` followed,
+// after any whitespace, by the `"` of its first key.
+fn starts_with_json_object(text: &str) -> bool {
+    text.strip_prefix('{')
+        .is_some_and(|rest| rest.trim_start().starts_with('"'))
+}
+
+// Finds the end of a JSON object starting at `start` by matching braces
+// outside of strings. The closing brace must be the last thing on its
+// line, so the body starts on the following line.
+fn split_json_front_matter(
+    content: &str,
+    start: usize,
+) -> Option<FrontMatterSplit<'_>> {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (index, character) in content[start..].char_indices() {
+        if in_string {
+            match character {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match character {
+            '"' => in_string = true,
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    let front_matter_end = start + index + 1;
+                    let mut lines =
+                        Lines { content, position: front_matter_end };
+                    let rest_of_line = lines.next().map_or("", |(line, _)| line);
+                    if !rest_of_line.trim().is_empty() {
+                        return None;
+                    }
+                    return Some(FrontMatterSplit {
+                        front_matter: Some(&content[start..front_matter_end]),
+                        format: FrontMatterFormat::Json,
+                        body: &content[lines.position..],
+                        body_offset: lines.position,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // The object was never closed
+    None
+}
+
+// Returns true if the line (without its line ending) is the given fence,
+// allowing trailing whitespace.
+fn is_fence(line: &str, fence: &str) -> bool {
+    line.trim_end() == fence
+}
+
+// Iterates over the lines of the content starting from a byte position,
+// yielding each line without its line ending together with its start
+// offset. `position` is left at the start of the following line.
+struct Lines<'a> {
+    content: &'a str,
+    position: usize,
+}
+
+impl<'a> Iterator for Lines<'a> {
+    type Item = (&'a str, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.position >= self.content.len() {
+            return None;
+        }
+
+        let line_start = self.position;
+        let rest = &self.content[line_start..];
+        let (line, line_length) = match rest.find('\n') {
+            Some(index) => (&rest[..index], index + 1),
+            None => (rest, rest.len()),
+        };
+        self.position += line_length;
+
+        Some((line.strip_suffix('\r').unwrap_or(line), line_start))
+    }
+}
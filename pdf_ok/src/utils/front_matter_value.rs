@@ -0,0 +1,123 @@
+use std::collections::BTreeMap;
+
+/// Parsed front matter: the top-level keys of a document and their
+/// values. BTreeMaps are automatically alphabetically sorted.
+pub type FrontMatter = BTreeMap<String, FrontMatterValue>;
+
+/// A front matter value, independent of the format (YAML, TOML or JSON)
+/// it was written in.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FrontMatterValue {
+    /// An empty value (`~` or `null`).
+    Null,
+    /// A boolean.
+    Bool(bool),
+    /// A whole number.
+    Integer(i64),
+    /// A floating point number.
+    Float(f64),
+    /// A string. TOML dates and times are stored as strings too.
+    String(String),
+    /// A list of values.
+    Sequence(Vec<FrontMatterValue>),
+    /// A nested set of keys and values.
+    Mapping(BTreeMap<String, FrontMatterValue>),
+}
+
+impl FrontMatterValue {
+    /// Returns the string if this is a [`FrontMatterValue::String`].
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            FrontMatterValue::String(string_value) => Some(string_value),
+            _ => None,
+        }
+    }
+}
+
+impl From<serde_yaml::Value> for FrontMatterValue {
+    fn from(value: serde_yaml::Value) -> Self {
+        use serde_yaml::Value;
+
+        match value {
+            Value::Null => FrontMatterValue::Null,
+            Value::Bool(bool_value) => FrontMatterValue::Bool(bool_value),
+            Value::Number(number) => match number.as_i64() {
+                Some(integer) => FrontMatterValue::Integer(integer),
+                None => FrontMatterValue::Float(number.as_f64().unwrap_or_default()),
+            },
+            Value::String(string_value) => FrontMatterValue::String(string_value),
+            Value::Sequence(sequence) => FrontMatterValue::Sequence(
+                sequence.into_iter().map(FrontMatterValue::from).collect(),
+            ),
+            Value::Mapping(mapping) => FrontMatterValue::Mapping(
+                mapping
+                    .into_iter()
+                    .map(|(key, value)| (yaml_key_to_string(key), value.into()))
+                    .collect(),
+            ),
+            // Tags such as `!custom value` are dropped, keeping the value
+            Value::Tagged(tagged) => tagged.value.into(),
+        }
+    }
+}
+
+impl From<toml::Value> for FrontMatterValue {
+    fn from(value: toml::Value) -> Self {
+        use toml::Value;
+
+        match value {
+            Value::Boolean(bool_value) => FrontMatterValue::Bool(bool_value),
+            Value::Integer(integer) => FrontMatterValue::Integer(integer),
+            Value::Float(float) => FrontMatterValue::Float(float),
+            Value::String(string_value) => FrontMatterValue::String(string_value),
+            Value::Datetime(datetime) => {
+                FrontMatterValue::String(datetime.to_string())
+            }
+            Value::Array(array) => FrontMatterValue::Sequence(
+                array.into_iter().map(FrontMatterValue::from).collect(),
+            ),
+            Value::Table(table) => FrontMatterValue::Mapping(
+                table.into_iter().map(|(key, value)| (key, value.into())).collect(),
+            ),
+        }
+    }
+}
+
+impl From<serde_json::Value> for FrontMatterValue {
+    fn from(value: serde_json::Value) -> Self {
+        use serde_json::Value;
+
+        match value {
+            Value::Null => FrontMatterValue::Null,
+            Value::Bool(bool_value) => FrontMatterValue::Bool(bool_value),
+            Value::Number(number) => match number.as_i64() {
+                Some(integer) => FrontMatterValue::Integer(integer),
+                None => FrontMatterValue::Float(number.as_f64().unwrap_or_default()),
+            },
+            Value::String(string_value) => FrontMatterValue::String(string_value),
+            Value::Array(array) => FrontMatterValue::Sequence(
+                array.into_iter().map(FrontMatterValue::from).collect(),
+            ),
+            Value::Object(object) => FrontMatterValue::Mapping(
+                object.into_iter().map(|(key, value)| (key, value.into())).collect(),
+            ),
+        }
+    }
+}
+
+// YAML allows non-string keys such as `2024: ...` or `true: ...`. They
+// are turned into their string form so every format has string keys.
+fn yaml_key_to_string(key: serde_yaml::Value) -> String {
+    use serde_yaml::Value;
+
+    match key {
+        Value::String(string_value) => string_value,
+        Value::Bool(bool_value) => bool_value.to_string(),
+        Value::Number(number) => number.to_string(),
+        Value::Null => "null".to_string(),
+        other => serde_yaml::to_string(&other)
+            .unwrap_or_default()
+            .trim_end()
+            .to_string(),
+    }
+}
@@ -1,203 +1,313 @@
 use colored::Colorize;
-use headless_chrome::Browser;
-use lopdf::{Document, Object as LopdfObject, StringFormat};
-use serde_yaml::Value;
+use lopdf::{dictionary, Document, Stream};
 use std::collections::BTreeMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+use crate::utils::{
+    format_pdf_date, make_deterministic, pdf_text_string, resolve_path,
+    xmp_packet, DocumentMetadata, FrontMatter, FrontMatterValue,
+    MetadataOptions, PageSetup, Renderer, ViewerPreferences,
+};
+use crate::Error;
+
+/// The producer written into the Info dictionary of every PDF.
+pub const PDF_PRODUCER: &str = "OrangeKiwi using lopdf";
 
-use crate::utils::extract_to_end_string;
 /// Generates a PDF from HTML content using headless Chrome.
 ///
 /// # Arguments
 ///
+/// * `renderer` - The renderer whose headless Chrome prints the page.
 /// * `generated_html` - The HTML content to convert to PDF.
-/// * `filename` - The name of the PDF file to generate.
+/// * `filename_path` - The path of the source file, used in errors.
+/// * `pdf_file_path` - The path to save the PDF to, usually from
+///   [`output_path`](crate::utils::output_path). Missing directories are
+///   created.
+/// * `front_matter` - The front matter used for the PDF metadata, the
+///   document language (`language`) and the viewer preferences.
+/// * `page_setup` - The paper size, orientation and margins to print with.
+/// * `metadata_options` - Which front matter values are written into the
+///   document information dictionary and the XMP metadata.
+///
+/// # Returns
+///
+/// The path of the saved PDF file.
 ///
 /// # Errors
 ///
-/// Returns a boxed error if there is an issue with the headless Chrome browser, navigation,
-/// capturing screenshot, printing to PDF, or writing the PDF file.
+/// Returns an [`Error`] if headless Chrome cannot be launched or fails to
+/// print the page, if [`post_process_pdf`] fails to update the printed
+/// PDF, or if the PDF file cannot be written.
 ///
 /// # Examples
 ///
-/// ```
-/// use your_crate_name::generate_pdf;
+/// ```no_run
+/// use std::collections::BTreeMap;
+/// use std::path::Path;
+///
+/// use pdf_ok::utils::{
+///     generate_pdf, FrontMatterValue, MetadataOptions, PageSetup, Renderer,
+/// };
 ///
 /// let generated_html = "<html><body><h1>Hello, world!</h1></body></html>".to_string();
-/// let filename = "example";
-/// let result = generate_pdf(generated_html, filename);
+/// let front_matter = BTreeMap::from([(
+///     "title".to_string(),
+///     FrontMatterValue::String("Example".to_string()),
+/// )]);
+/// let renderer = Renderer::new();
+/// let result = generate_pdf(
+///     &renderer,
+///     generated_html,
+///     Path::new("example.md"),
+///     Path::new("pdfs/example.pdf"),
+///     front_matter,
+///     &PageSetup::default(),
+///     &MetadataOptions::default(),
+/// );
 /// assert!(result.is_ok());
+/// renderer.shutdown();
 /// ```
 pub fn generate_pdf(
+    renderer: &Renderer,
     generated_html: String,
-    filename_path: &str,
-    yaml_btreemap: BTreeMap<String, Value>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let mut string_values_btreemap: BTreeMap<String, String> = BTreeMap::new();
-    for (key, value) in yaml_btreemap {
-        if let Value::String(string_value) = value {
-            string_values_btreemap.insert(key, string_value);
-        }
-    }
-
-    let browser = Browser::default()?; // Start a new headless Chrome browser instance
-    let tab = browser.new_tab()?; // Open a new tab
+    filename_path: &Path,
+    pdf_file_path: &Path,
+    front_matter: FrontMatter,
+    page_setup: &PageSetup,
+    metadata_options: &MetadataOptions,
+) -> Result<PathBuf, Error> {
+    // Open a new tab in the shared headless Chrome instance
+    let tab = renderer.new_tab(filename_path)?;
 
     let mut html = String::new();
     // Encode the HTML content to URL-safe format
     // url_escape:: comes from the url_escape crate
     url_escape::encode_query_to_string(generated_html, &mut html);
 
-    // TODO RL Allow path to be set by the user, keeping "pdfs" as a fallback/default location
-    let output_directory = "pdfs";
-    fs::create_dir_all(output_directory)?;
-    let extracted_filename = extract_to_end_string(filename_path, '/');
-    let mut pdf_file = extracted_filename.unwrap().to_string();
-    pdf_file.push_str(".pdf");
-
-    let pdf_file_path = Path::new(output_directory).join(pdf_file);
+    if let Some(output_directory) = pdf_file_path.parent() {
+        fs::create_dir_all(output_directory).map_err(|source| Error::Io {
+            path: output_directory.to_path_buf(),
+            source,
+        })?;
+    }
 
     // Navigate the tab to the HTML content.
     // In this case, the page is a data stream
     tab.navigate_to(
         format!("data:text/html;charset=utf-8,{}", html).as_str(),
-    )?;
+    )
+    .map_err(|error| render_error(filename_path, error))?;
 
     // Convert the page to PDF format
-    let pdf = tab.print_to_pdf(None)?;
+    let pdf = tab
+        .print_to_pdf(Some(page_setup.print_options()))
+        .map_err(|error| render_error(filename_path, error))?;
     // println!("{:?}", String::from_utf8_lossy(&pdf));
 
-    // Create a new PDF document
-    let mut doc: Document = Document::load_mem(&pdf)?;
+    // Write the metadata into the printed PDF
+    let pdf = post_process_pdf(
+        &pdf,
+        &front_matter,
+        metadata_options,
+        filename_path,
+    )?;
+
+    fs::write(pdf_file_path, pdf).map_err(|source| Error::Save {
+        path: pdf_file_path.to_path_buf(),
+        source,
+    })?;
+
+    Ok(pdf_file_path.to_path_buf())
+}
+
+/// Updates the metadata of a PDF printed by Chrome: the Info dictionary
+/// entries mapped from the front matter, the producer and dates, the
+/// Catalog's `/Lang` and viewer preferences, and the XMP packet. In
+/// [`deterministic`](MetadataOptions::deterministic) mode the producer
+/// and dates replace any mapped values, and the document is also passed
+/// through [`make_deterministic`].
+///
+/// # Arguments
+///
+/// * `pdf` - The PDF as printed.
+/// * `front_matter` - The front matter of the source file.
+/// * `metadata_options` - Which front matter values are written into the
+///   document information dictionary and the XMP metadata.
+/// * `filename_path` - The path of the source file, used in errors.
+///
+/// # Returns
+///
+/// The updated PDF.
+///
+/// # Errors
+///
+/// Returns an [`Error`] if the PDF cannot be read or updated, if the
+/// viewer preferences in the front matter are not valid, or if
+/// a metadata field whose missing key policy is
+/// [`Fail`](crate::utils::MissingMetadataPolicy::Fail) has no front
+/// matter value.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::BTreeMap;
+/// use std::path::Path;
+///
+/// use lopdf::{dictionary, Document};
+/// use pdf_ok::utils::{post_process_pdf, DocumentMetadata, MetadataOptions};
+///
+/// let mut doc = Document::with_version("1.4");
+/// let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog" });
+/// doc.trailer.set("Root", catalog_id);
+/// let mut pdf = Vec::new();
+/// doc.save_to(&mut pdf).unwrap();
+///
+/// let options = MetadataOptions { deterministic: true, ..MetadataOptions::default() };
+/// let pdf = post_process_pdf(&pdf, &BTreeMap::new(), &options, Path::new("a.md"))
+///     .unwrap();
+///
+/// let doc = Document::load_mem(&pdf).unwrap();
+/// assert_eq!(doc.metadata("CreationDate").as_deref(), Some("D:19700101000000+00'00'"));
+/// assert!(doc.catalog().unwrap().has(b"Metadata"));
+/// ```
+pub fn post_process_pdf(
+    pdf: &[u8],
+    front_matter: &FrontMatter,
+    metadata_options: &MetadataOptions,
+    filename_path: &Path,
+) -> Result<Vec<u8>, Error> {
+    let post_process_error = |source| Error::PdfPostProcess {
+        path: filename_path.to_path_buf(),
+        source,
+    };
+    let viewer_preferences = ViewerPreferences::default()
+        .with_front_matter(front_matter)
+        .map_err(|message| Error::ViewerPreferences {
+            path: filename_path.to_path_buf(),
+            message,
+        })?;
+    let dates = metadata_options.dates(front_matter, filename_path);
+
+    let mut doc = Document::load_mem(pdf).map_err(post_process_error)?;
     doc.version = "1.7".to_string();
 
-    #[allow(unused_variables)]
-    let mut object_count: i32 = 0;
-    // Iterate over the objects in the PDF document and count them
-    for object_element in &mut doc.objects {
-        let (_key, object) = object_element;
-        match object {
-            LopdfObject::Dictionary(dictionary) => {
-                // Variable to track if Creator key is present
-                let mut creator_found = false;
-
-                // Print out the dictionary entries
-                for (key, value) in dictionary.iter_mut() {
-                    let ascii_key = String::from_utf8_lossy(key);
-
-                    // Print out all Key/Value pairs
-                    // println!(
-                    //     " {} {} :{} {:#?}",
-                    //     "key".cyan(),
-                    //     ascii_key,
-                    //     "value".yellow(),
-                    //     value
-                    // );
-
-                    // Iterate over the key-value pairs in the dictionary
-                    // Check if the key is "Creator"
-                    if ascii_key == "Creator" {
-                        // Update the value associated with the key
-                        let ascii_string =
-                            string_values_btreemap.get("generator").unwrap();
-                        let ascii_bytes: Vec<u8> =
-                            ascii_string.as_bytes().to_vec();
-                        *value = lopdf::Object::String(
-                            ascii_bytes,
-                            StringFormat::Literal,
-                        );
-                        // Set creator_found to true
-                        creator_found = true;
-                    }
-                    if ascii_key == "Producer" {
-                        // Update the value associated with the key
-                        let ascii_string = "OrangeKiwi using lopdf";
-                        let ascii_bytes: Vec<u8> =
-                            ascii_string.as_bytes().to_vec();
-                        *value = lopdf::Object::String(
-                            ascii_bytes,
-                            StringFormat::Literal,
-                        );
-                    }
-                }
-                // If Creator key was found, add/update various PDF properties/meta-data
-                if creator_found {
-                    // Insert or update Title key
-                    let (key, value) = populate_dictionary(
-                        "Title".to_string(),
-                        string_values_btreemap.clone(),
-                    );
-                    dictionary.set(key, value);
-                    // Insert or update Author key
-                    let (key, value) = populate_dictionary(
-                        "Author".to_string(),
-                        string_values_btreemap.clone(),
-                    );
-                    dictionary.set(key, value);
-                    // Insert or update Subject key
-                    let (key, value) = populate_dictionary(
-                        "Subject".to_string(),
-                        string_values_btreemap.clone(),
-                    );
-                    dictionary.set(key, value);
-                    // Insert or update Keywords key
-                    let (key, value) = populate_dictionary(
-                        "Keywords".to_string(),
-                        string_values_btreemap.clone(),
-                    );
-                    dictionary.set(key, value);
-                    // Insert or update Language key
-                    let (key, value) = populate_dictionary(
-                        "Language".to_string(),
-                        string_values_btreemap.clone(),
-                    );
-                    dictionary.set(key, value);
-                    // Insert or update Permalink key
-                    let (key, value) = populate_dictionary(
-                        "Permalink".to_string(),
-                        string_values_btreemap.clone(),
-                    );
-                    dictionary.set(key, value);
-                    // Insert or update Site_components key
-                    let (key, value) = populate_dictionary(
-                        "Site_components".to_string(),
-                        string_values_btreemap.clone(),
-                    );
-                    dictionary.set(key, value);
-                }
-
-                object_count += 1;
-            }
-            LopdfObject::Stream(_) => {
-                // It's a stream object
-                object_count += 1;
-            }
-            _ => {
-                // It's some other type of object
-            }
+    // Update the Info dictionary the trailer refers to, which is created
+    // if Chrome wrote none
+    doc.set_metadata("Producer", PDF_PRODUCER);
+    for entry in &metadata_options.doc_info_entries {
+        // A missing value the policy skips keeps whatever Chrome wrote
+        if let Some(value) = entry.value(front_matter, filename_path)? {
+            println!(
+                "{}: {}",
+                "Populate PDF dictionary key".yellow(),
+                entry.doc_info_entry.cyan()
+            );
+            doc.set_metadata(&entry.doc_info_entry, &value);
         }
     }
+    // A mapping cannot change the producer of a reproducible build
+    if metadata_options.deterministic {
+        doc.set_metadata("Producer", PDF_PRODUCER);
+    }
 
-    doc.save(pdf_file_path)
-        .expect("Failed to save modified PDF document");
+    // Replace the time of printing with the dates of the document
+    if let Some(creation) = dates.creation {
+        doc.set_metadata("CreationDate", &format_pdf_date(&creation));
+    }
+    if let Some(modification) = dates.modification {
+        doc.set_metadata("ModDate", &format_pdf_date(&modification));
+    }
 
-    Ok(())
+    set_catalog_entries(
+        &mut doc,
+        front_matter,
+        &viewer_preferences,
+        filename_path,
+    )?;
+    attach_xmp_metadata(
+        &mut doc,
+        front_matter,
+        metadata_options,
+        filename_path,
+    )?;
+
+    if metadata_options.deterministic {
+        make_deterministic(&mut doc);
+    }
+
+    let mut bytes = Vec::new();
+    doc.save_to(&mut bytes)
+        .map_err(|source| post_process_error(lopdf::Error::IO(source)))?;
+    Ok(bytes)
+}
+
+// Wraps an error reported by headless Chrome while rendering a document.
+fn render_error(filename_path: &Path, error: impl std::fmt::Display) -> Error {
+    Error::Render { path: filename_path.to_path_buf(), message: error.to_string() }
 }
 
-fn populate_dictionary(
-    yaml_entry: String,
-    string_values_btreemap: BTreeMap<String, String>,
-) -> (Vec<u8>, LopdfObject) {
-    println!("{}: {}", "Populate PDF dictionary key".yellow(), yaml_entry.cyan());
-    let yaml_entry: String = match yaml_entry.as_str() {
-        "Subject" => "Description".to_string(),
-        _ => yaml_entry
+// Sets the Catalog's /Lang from the `language` front matter key, which
+// screen readers use, and writes the viewer preferences.
+fn set_catalog_entries(
+    doc: &mut Document,
+    front_matter: &FrontMatter,
+    viewer_preferences: &ViewerPreferences,
+    filename_path: &Path,
+) -> Result<(), Error> {
+    let post_process_error = |source| Error::PdfPostProcess {
+        path: filename_path.to_path_buf(),
+        source,
     };
-    let key = yaml_entry.as_bytes().to_vec();
-    let value_string =
-        string_values_btreemap.get(&yaml_entry.to_lowercase()).unwrap();
-    let value_as_bytes: Vec<u8> = value_string.as_bytes().to_vec();
 
-    (key, LopdfObject::String(value_as_bytes, StringFormat::Literal))
+    let language = resolve_path(front_matter, "language")
+        .and_then(FrontMatterValue::as_str)
+        .map(str::trim)
+        .filter(|language| !language.is_empty());
+    if let Some(language) = language {
+        doc.catalog_mut()
+            .map_err(post_process_error)?
+            .set("Lang", pdf_text_string(language));
+    }
+    viewer_preferences.apply(doc).map_err(post_process_error)
+}
+
+// Adds an XMP packet built from the final Info dictionary values and the
+// custom XMP properties, and points the Catalog's /Metadata entry at it.
+fn attach_xmp_metadata(
+    doc: &mut Document,
+    front_matter: &FrontMatter,
+    metadata_options: &MetadataOptions,
+    filename_path: &Path,
+) -> Result<(), Error> {
+    let custom_values: Vec<_> = metadata_options
+        .xmp_properties
+        .iter()
+        .filter_map(|property| {
+            property.value(front_matter).map(|value| (property, value))
+        })
+        .collect();
+    let info_values: BTreeMap<String, String> =
+        doc.metadata_entries().into_iter().collect();
+    let packet = xmp_packet(
+        &info_values,
+        &custom_values,
+        &metadata_options.xmp_namespaces,
+    );
+
+    // XMP is left uncompressed, so tools that scan for it can find it
+    let mut stream = Stream::new(
+        dictionary! { "Type" => "Metadata", "Subtype" => "XML" },
+        packet.into_bytes(),
+    );
+    stream.allows_compression = false;
+    let metadata_id = doc.add_object(stream);
+
+    doc.catalog_mut()
+        .map_err(|source| Error::PdfPostProcess {
+            path: filename_path.to_path_buf(),
+            source,
+        })?
+        .set("Metadata", metadata_id);
+    Ok(())
 }
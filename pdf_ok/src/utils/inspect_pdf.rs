@@ -0,0 +1,47 @@
+use colored::Colorize;
+use lopdf::Document;
+use std::path::Path;
+
+use crate::utils::DocumentMetadata;
+
+/// Prints a summary of an existing PDF file: its PDF version, page count
+/// and the entries of its document information dictionary.
+///
+/// # Arguments
+///
+/// * `pdf_path` - The path of the PDF file to inspect.
+///
+/// # Errors
+///
+/// Returns a boxed error if the file cannot be read or is not a valid
+/// PDF document.
+///
+/// # Examples
+///
+/// ```no_run
+/// use pdf_ok::utils::inspect_pdf;
+///
+/// inspect_pdf("pdfs/404.pdf").unwrap();
+/// ```
+pub fn inspect_pdf<P: AsRef<Path>>(
+    pdf_path: P,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let pdf_path = pdf_path.as_ref();
+    let doc = Document::load(pdf_path)?;
+
+    println!("{}", pdf_path.display().to_string().bright_yellow());
+    println!("{}: {}", "PDF version".cyan(), doc.version);
+    println!("{}: {}", "Pages".cyan(), doc.get_pages().len());
+
+    // The document information dictionary is referenced from the trailer
+    if doc.info_dictionary().is_some() {
+        println!("{}", "Document information".cyan());
+        for (key, value) in doc.metadata_entries() {
+            println!("  {}: {}", key, value);
+        }
+    } else {
+        println!("{}", "No document information dictionary".yellow());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,220 @@
+use colored::Colorize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::utils::{
+    render_markdown_template, FrontMatter, FrontMatterValue, MergedMarkdown,
+    TemplateError, TemplateOptions,
+};
+use crate::Error;
+
+/// The directory layouts are read from when none is configured.
+pub const DEFAULT_LAYOUTS_DIRECTORY: &str = "./layouts";
+
+/// The layout used when the front matter has no `layout` key, or the
+/// layout it names does not exist.
+pub const DEFAULT_LAYOUT: &str = "default";
+
+// The layout used when the layouts directory has no `default.html`
+const BUILT_IN_LAYOUT: &str = include_str!("../../layouts/default.html");
+
+/// An HTML template that wraps the rendered body of a document.
+///
+/// A layout uses the same `{{placeholder}}`, `{% if %}` and `{% for %}`
+/// syntax as the Markdown content. Besides the front matter values it
+/// can use:
+///
+/// * `content` - the rendered HTML body of the document.
+/// * `charset` - the `charset` front matter value, or `utf-8`.
+/// * `language` - the `language` front matter value, or `en`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    /// The name of the layout, e.g. `page` for `layouts/page.html`.
+    pub name: String,
+    /// The file the layout was read from, or `None` for the built-in
+    /// default layout.
+    pub path: Option<PathBuf>,
+    /// The HTML template.
+    pub source: String,
+}
+
+impl Layout {
+    /// Returns the built-in default layout, a plain HTML page with the
+    /// `title`, `description` and `author` in its `<head>`.
+    pub fn built_in() -> Self {
+        Layout {
+            name: DEFAULT_LAYOUT.to_string(),
+            path: None,
+            source: BUILT_IN_LAYOUT.to_string(),
+        }
+    }
+
+    /// Returns a name for the layout suitable for error messages: its
+    /// file path, or `<built-in default layout>`.
+    pub fn display_path(&self) -> PathBuf {
+        match &self.path {
+            Some(path) => path.clone(),
+            None => PathBuf::from("<built-in default layout>"),
+        }
+    }
+}
+
+/// Selects the layout for a document from its `layout` front matter key.
+///
+/// The layout `name` is read from `<layouts_directory>/<name>.html`. If
+/// the key is missing, or names a layout that does not exist (which is
+/// reported as a warning), `default.html` in the layouts directory is
+/// used, and failing that the [built-in layout](Layout::built_in).
+/// Names containing `/`, `\` or `..` could point outside the layouts
+/// directory, so they are also reported and replaced by the default.
+///
+/// # Arguments
+///
+/// * `front_matter` - The front matter of the document.
+/// * `layouts_directory` - The directory containing the layouts.
+///
+/// # Errors
+///
+/// Returns an [`Error::Io`] if a layout file exists but cannot be read.
+///
+/// # Examples
+///
+/// ```
+/// use pdf_ok::utils::{
+///     parse_front_matter, select_layout, FrontMatterFormat,
+/// };
+///
+/// let front_matter =
+///     parse_front_matter("title: Example", FrontMatterFormat::Yaml).unwrap();
+/// let layout = select_layout(&front_matter, "no_such_directory").unwrap();
+/// assert_eq!(layout.name, "default");
+/// assert!(layout.path.is_none());
+///
+/// let front_matter =
+///     parse_front_matter("layout: ../../secret", FrontMatterFormat::Yaml)
+///         .unwrap();
+/// let layout = select_layout(&front_matter, "no_such_directory").unwrap();
+/// assert_eq!(layout.name, "default");
+/// ```
+pub fn select_layout<P: AsRef<Path>>(
+    front_matter: &FrontMatter,
+    layouts_directory: P,
+) -> Result<Layout, Error> {
+    let layouts_directory = layouts_directory.as_ref();
+
+    if let Some(name) = front_matter.get("layout").and_then(FrontMatterValue::as_str)
+    {
+        if !is_layout_name(name) {
+            println!(
+                "{} layout \"{}\" is not a file name, using the default layout",
+                "warning".bright_yellow(),
+                name
+            );
+        } else if let Some(layout) = read_layout(layouts_directory, name)? {
+            return Ok(layout);
+        } else if name != DEFAULT_LAYOUT {
+            println!(
+                "{} layout \"{}\" not found in {}, using the default layout",
+                "warning".bright_yellow(),
+                name,
+                layouts_directory.display()
+            );
+        }
+    }
+
+    Ok(read_layout(layouts_directory, DEFAULT_LAYOUT)?
+        .unwrap_or_else(Layout::built_in))
+}
+
+/// Wraps the rendered HTML body of a document in a layout.
+///
+/// # Arguments
+///
+/// * `layout` - The layout to use, usually from [`select_layout`].
+/// * `content` - The rendered HTML body.
+/// * `front_matter` - The front matter values of the document.
+/// * `options` - How values are formatted and missing keys handled.
+///
+/// # Errors
+///
+/// Returns a [`TemplateError`] with a line number in the layout for
+/// malformed tags, unknown filters or, under
+/// [`MissingKeyPolicy::Fail`](crate::utils::MissingKeyPolicy::Fail),
+/// missing keys.
+///
+/// # Examples
+///
+/// ```
+/// use pdf_ok::utils::{
+///     apply_layout, parse_front_matter, FrontMatterFormat, Layout,
+///     TemplateOptions,
+/// };
+///
+/// let layout = Layout {
+///     name: "plain".to_string(),
+///     path: None,
+///     source: "<html lang=\"{{language}}\"><title>{{title}}</title>{{content}}</html>"
+///         .to_string(),
+/// };
+/// let front_matter =
+///     parse_front_matter("title: Example", FrontMatterFormat::Yaml).unwrap();
+/// let html = apply_layout(
+///     &layout,
+///     "<p>Hello</p>",
+///     &front_matter,
+///     &TemplateOptions::default(),
+/// )
+/// .unwrap();
+/// assert_eq!(
+///     html.content,
+///     "<html lang=\"en\"><title>Example</title><p>Hello</p></html>"
+/// );
+/// ```
+pub fn apply_layout(
+    layout: &Layout,
+    content: &str,
+    front_matter: &FrontMatter,
+    options: &TemplateOptions,
+) -> Result<MergedMarkdown, TemplateError> {
+    let mut values = front_matter.clone();
+    values.insert(
+        "content".to_string(),
+        FrontMatterValue::String(content.to_string()),
+    );
+    for (key, default) in [("charset", "utf-8"), ("language", "en")] {
+        let is_set = values
+            .get(key)
+            .and_then(FrontMatterValue::as_str)
+            .is_some_and(|value| !value.trim().is_empty());
+        if !is_set {
+            values.insert(
+                key.to_string(),
+                FrontMatterValue::String(default.to_string()),
+            );
+        }
+    }
+
+    render_markdown_template(&values, &layout.source, options)
+}
+
+// Reads `<name>.html` from the layouts directory, if it exists.
+fn read_layout(
+    layouts_directory: &Path,
+    name: &str,
+) -> Result<Option<Layout>, Error> {
+    let path = layouts_directory.join(format!("{}.html", name));
+    if !path.is_file() {
+        return Ok(None);
+    }
+
+    let source = fs::read_to_string(&path)
+        .map_err(|source| Error::Io { path: path.clone(), source })?;
+
+    Ok(Some(Layout { name: name.to_string(), path: Some(path), source }))
+}
+
+// Whether a layout name names a file directly inside the layouts
+// directory.
+fn is_layout_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains(['/', '\\']) && !name.contains("..")
+}
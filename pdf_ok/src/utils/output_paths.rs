@@ -0,0 +1,193 @@
+use std::path::{Component, Path, PathBuf};
+
+use crate::utils::{
+    merge_markdown_yaml, FrontMatter, FrontMatterValue, MissingKeyPolicy,
+    TemplateOptions, DEFAULT_SOURCE_DIRECTORY,
+};
+use crate::Error;
+
+/// The directory PDFs are written to when none is configured.
+pub const DEFAULT_OUTPUT_DIRECTORY: &str = "./pdfs";
+
+/// The file name template used when none is configured: the name of the
+/// source file with a `.pdf` extension.
+pub const DEFAULT_FILENAME_TEMPLATE: &str = "{{file_stem}}.pdf";
+
+/// Where the generated PDFs are written and how they are named.
+///
+/// # Examples
+///
+/// ```
+/// use pdf_ok::utils::OutputOptions;
+///
+/// let options = OutputOptions {
+///     directory: "build/pdfs".into(),
+///     filename_template: "{{date}}-{{short_name}}.pdf".to_string(),
+///     ..OutputOptions::default()
+/// };
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputOptions {
+    /// The root directory the PDFs are written to.
+    pub directory: PathBuf,
+    /// The template for the file name of each PDF. It can use the front
+    /// matter values and `file_stem`, the name of the source file without
+    /// its extension.
+    pub filename_template: String,
+    /// The directory whose structure is mirrored under
+    /// [`directory`](OutputOptions::directory). Source files outside it
+    /// are written to the output root.
+    pub source_root: PathBuf,
+}
+
+impl Default for OutputOptions {
+    fn default() -> Self {
+        OutputOptions {
+            directory: PathBuf::from(DEFAULT_OUTPUT_DIRECTORY),
+            filename_template: DEFAULT_FILENAME_TEMPLATE.to_string(),
+            source_root: PathBuf::from(DEFAULT_SOURCE_DIRECTORY),
+        }
+    }
+}
+
+/// Works out the path of the PDF generated from a source file.
+///
+/// The directory of the source file relative to the source root is
+/// mirrored under the output directory, so `index.md` files in different
+/// folders do not overwrite each other. The file name comes from the
+/// filename template, with any characters that are not valid in file
+/// names replaced by `-`, and a `.pdf` extension added if it is missing.
+///
+/// # Arguments
+///
+/// * `source_path` - The path of the source file.
+/// * `front_matter` - The front matter of the source file.
+/// * `options` - The output directory, filename template and source root.
+///
+/// # Errors
+///
+/// Returns an [`Error::OutputPath`] if the filename template uses a key
+/// that is not in the front matter, or is malformed.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+///
+/// use pdf_ok::utils::{
+///     output_path, parse_front_matter, FrontMatterFormat, OutputOptions,
+/// };
+///
+/// let front_matter = parse_front_matter(
+///     "date: 2023-07-12\nshort_name: \"Kaishi: Features\"",
+///     FrontMatterFormat::Yaml,
+/// )
+/// .unwrap();
+/// let options = OutputOptions {
+///     directory: "pdfs".into(),
+///     filename_template: "{{date}}-{{short_name}}".to_string(),
+///     source_root: "docs".into(),
+/// };
+///
+/// let path = output_path(Path::new("docs/guide/index.md"), &front_matter, &options)
+///     .unwrap();
+/// assert_eq!(path, Path::new("pdfs/guide/2023-07-12-Kaishi- Features.pdf"));
+/// ```
+pub fn output_path(
+    source_path: &Path,
+    front_matter: &FrontMatter,
+    options: &OutputOptions,
+) -> Result<PathBuf, Error> {
+    let file_stem = source_path
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+
+    let mut values = front_matter.clone();
+    values.insert(
+        "file_stem".to_string(),
+        FrontMatterValue::String(file_stem.clone()),
+    );
+    let template_options = TemplateOptions {
+        missing_key_policy: MissingKeyPolicy::Fail,
+        ..TemplateOptions::default()
+    };
+    let file_name = merge_markdown_yaml(
+        &values,
+        &options.filename_template,
+        &template_options,
+    )
+    .map_err(|error| Error::OutputPath {
+        path: source_path.to_path_buf(),
+        message: format!("filename template: {}", error.message),
+    })?
+    .content;
+
+    // Sanitise the name without its extension, so `.pdf` is never trimmed
+    let mut file_name = sanitize_file_name(&file_name);
+    if file_name.to_lowercase().ends_with(".pdf") {
+        file_name = sanitize_file_name(&file_name[..file_name.len() - 4]);
+    }
+    if file_name.is_empty() {
+        file_name = sanitize_file_name(&file_stem);
+    }
+    file_name.push_str(".pdf");
+
+    Ok(options
+        .directory
+        .join(mirrored_directory(source_path, &options.source_root))
+        .join(file_name))
+}
+
+/// Replaces the characters that are not valid in file names on common
+/// file systems (`/ \ : * ? " < > |` and control characters) with `-`,
+/// and trims spaces and dots from both ends.
+///
+/// # Examples
+///
+/// ```
+/// use pdf_ok::utils::sanitize_file_name;
+///
+/// assert_eq!(sanitize_file_name(" What? A/B: test. "), "What- A-B- test");
+/// ```
+pub fn sanitize_file_name(file_name: &str) -> String {
+    let sanitized: String = file_name
+        .chars()
+        .map(|character| match character {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '-',
+            _ if character.is_control() => '-',
+            _ => character,
+        })
+        .collect();
+
+    sanitized
+        .trim_matches(|character| character == ' ' || character == '.')
+        .to_string()
+}
+
+// Returns the directory of the source file relative to the source root,
+// or an empty path if it is not inside it.
+fn mirrored_directory(source_path: &Path, source_root: &Path) -> PathBuf {
+    let parent = normalize(source_path.parent().unwrap_or(Path::new("")));
+    let source_root = normalize(source_root);
+
+    match parent.strip_prefix(&source_root) {
+        // Never let `..` or an absolute path lead outside the output root
+        Ok(relative)
+            if relative
+                .components()
+                .all(|component| matches!(component, Component::Normal(_))) =>
+        {
+            relative.to_path_buf()
+        }
+        _ => PathBuf::new(),
+    }
+}
+
+// Drops the `.` components of a path, so `./docs` and `docs` compare equal.
+fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|component| !matches!(component, Component::CurDir))
+        .collect()
+}
@@ -0,0 +1,411 @@
+use headless_chrome::types::PrintToPdfOptions;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::utils::{FrontMatter, FrontMatterValue};
+
+/// A unit of length used for paper sizes and margins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LengthUnit {
+    /// Millimetres, written `mm`. Used for numbers without a unit.
+    Millimetre,
+    /// Inches, written `in`.
+    Inch,
+    /// PostScript points (1/72 inch), written `pt`.
+    Point,
+}
+
+/// A length with its unit, e.g. `12.5mm`, `0.5in` or `36pt`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Length {
+    /// The number of units.
+    pub value: f64,
+    /// The unit of the value.
+    pub unit: LengthUnit,
+}
+
+impl Length {
+    /// Creates a length in millimetres.
+    pub const fn mm(value: f64) -> Self {
+        Length { value, unit: LengthUnit::Millimetre }
+    }
+
+    /// Creates a length in inches.
+    pub const fn inches(value: f64) -> Self {
+        Length { value, unit: LengthUnit::Inch }
+    }
+
+    /// Creates a length in points.
+    pub const fn pt(value: f64) -> Self {
+        Length { value, unit: LengthUnit::Point }
+    }
+
+    /// Returns the length in inches, the unit Chrome prints with.
+    pub fn to_inches(self) -> f64 {
+        match self.unit {
+            LengthUnit::Millimetre => self.value / 25.4,
+            LengthUnit::Inch => self.value,
+            LengthUnit::Point => self.value / 72.0,
+        }
+    }
+}
+
+impl FromStr for Length {
+    type Err = String;
+
+    /// Reads a length such as `10`, `12.5mm`, `0.5in` or `36pt`. A
+    /// number without a unit is in millimetres.
+    fn from_str(length: &str) -> Result<Self, Self::Err> {
+        let trimmed = length.trim();
+        let lowercase = trimmed.to_lowercase();
+        let (number, unit) = if let Some(number) = lowercase.strip_suffix("mm") {
+            (number, LengthUnit::Millimetre)
+        } else if let Some(number) = lowercase.strip_suffix("in") {
+            (number, LengthUnit::Inch)
+        } else if let Some(number) = lowercase.strip_suffix("pt") {
+            (number, LengthUnit::Point)
+        } else {
+            (lowercase.as_str(), LengthUnit::Millimetre)
+        };
+
+        match number.trim().parse::<f64>() {
+            Ok(value) if value.is_finite() && value >= 0.0 => {
+                Ok(Length { value, unit })
+            }
+            _ => Err(format!(
+                "invalid length \"{}\" (expected a positive number followed by mm, in or pt)",
+                trimmed
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Length {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unit = match self.unit {
+            LengthUnit::Millimetre => "mm",
+            LengthUnit::Inch => "in",
+            LengthUnit::Point => "pt",
+        };
+        write!(f, "{}{}", self.value, unit)
+    }
+}
+
+/// The size of the paper a document is printed on: the ISO A and B
+/// series, US Letter and Legal, or custom dimensions.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum PaperSize {
+    A0,
+    A1,
+    A2,
+    A3,
+    #[default]
+    A4,
+    A5,
+    A6,
+    A7,
+    A8,
+    A9,
+    A10,
+    B0,
+    B1,
+    B2,
+    B3,
+    B4,
+    B5,
+    B6,
+    B7,
+    B8,
+    B9,
+    B10,
+    /// US Letter, 8.5 × 11 inches.
+    Letter,
+    /// US Legal, 8.5 × 14 inches.
+    Legal,
+    /// Any other size, given as the portrait width and height.
+    Custom {
+        /// The width of the paper.
+        width: Length,
+        /// The height of the paper.
+        height: Length,
+    },
+}
+
+// The portrait width and height of each named paper size
+const PAPER_SIZES: [(&str, PaperSize, Length, Length); 24] = [
+    ("a0", PaperSize::A0, Length::mm(841.0), Length::mm(1189.0)),
+    ("a1", PaperSize::A1, Length::mm(594.0), Length::mm(841.0)),
+    ("a2", PaperSize::A2, Length::mm(420.0), Length::mm(594.0)),
+    ("a3", PaperSize::A3, Length::mm(297.0), Length::mm(420.0)),
+    ("a4", PaperSize::A4, Length::mm(210.0), Length::mm(297.0)),
+    ("a5", PaperSize::A5, Length::mm(148.0), Length::mm(210.0)),
+    ("a6", PaperSize::A6, Length::mm(105.0), Length::mm(148.0)),
+    ("a7", PaperSize::A7, Length::mm(74.0), Length::mm(105.0)),
+    ("a8", PaperSize::A8, Length::mm(52.0), Length::mm(74.0)),
+    ("a9", PaperSize::A9, Length::mm(37.0), Length::mm(52.0)),
+    ("a10", PaperSize::A10, Length::mm(26.0), Length::mm(37.0)),
+    ("b0", PaperSize::B0, Length::mm(1000.0), Length::mm(1414.0)),
+    ("b1", PaperSize::B1, Length::mm(707.0), Length::mm(1000.0)),
+    ("b2", PaperSize::B2, Length::mm(500.0), Length::mm(707.0)),
+    ("b3", PaperSize::B3, Length::mm(353.0), Length::mm(500.0)),
+    ("b4", PaperSize::B4, Length::mm(250.0), Length::mm(353.0)),
+    ("b5", PaperSize::B5, Length::mm(176.0), Length::mm(250.0)),
+    ("b6", PaperSize::B6, Length::mm(125.0), Length::mm(176.0)),
+    ("b7", PaperSize::B7, Length::mm(88.0), Length::mm(125.0)),
+    ("b8", PaperSize::B8, Length::mm(62.0), Length::mm(88.0)),
+    ("b9", PaperSize::B9, Length::mm(44.0), Length::mm(62.0)),
+    ("b10", PaperSize::B10, Length::mm(31.0), Length::mm(44.0)),
+    ("letter", PaperSize::Letter, Length::inches(8.5), Length::inches(11.0)),
+    ("legal", PaperSize::Legal, Length::inches(8.5), Length::inches(14.0)),
+];
+
+impl PaperSize {
+    /// Returns the portrait width and height of the paper.
+    pub fn dimensions(self) -> (Length, Length) {
+        if let PaperSize::Custom { width, height } = self {
+            return (width, height);
+        }
+        PAPER_SIZES
+            .iter()
+            .find(|(_, size, _, _)| *size == self)
+            .map(|(_, _, width, height)| (*width, *height))
+            .unwrap_or((Length::mm(210.0), Length::mm(297.0)))
+    }
+}
+
+impl FromStr for PaperSize {
+    type Err = String;
+
+    /// Reads a paper size name such as `A4`, `b5`, `Letter` or `legal`,
+    /// or custom dimensions written `WIDTH x HEIGHT`, e.g. `100mm x 150mm`
+    /// or `6inx9in`.
+    fn from_str(paper_size: &str) -> Result<Self, Self::Err> {
+        let name = paper_size.trim().to_lowercase();
+        if let Some((_, size, _, _)) =
+            PAPER_SIZES.iter().find(|(size_name, _, _, _)| *size_name == name)
+        {
+            return Ok(*size);
+        }
+
+        match name.split_once('x') {
+            Some((width, height)) => {
+                let width: Length = width.parse()?;
+                let height: Length = height.parse()?;
+                if width.value == 0.0 || height.value == 0.0 {
+                    return Err(format!(
+                        "invalid paper size \"{}\" (width and height must be greater than zero)",
+                        paper_size.trim()
+                    ));
+                }
+                Ok(PaperSize::Custom { width, height })
+            }
+            None => Err(format!(
+                "unknown paper size \"{}\" (expected A0-A10, B0-B10, Letter, Legal or WIDTHxHEIGHT)",
+                paper_size.trim()
+            )),
+        }
+    }
+}
+
+/// Which way up the paper is printed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PaperOrientation {
+    /// The long side is vertical.
+    #[default]
+    Portrait,
+    /// The long side is horizontal.
+    Landscape,
+}
+
+impl FromStr for PaperOrientation {
+    type Err = String;
+
+    fn from_str(orientation: &str) -> Result<Self, Self::Err> {
+        match orientation.trim().to_lowercase().as_str() {
+            "portrait" => Ok(PaperOrientation::Portrait),
+            "landscape" => Ok(PaperOrientation::Landscape),
+            _ => Err(format!(
+                "unknown orientation \"{}\" (expected portrait or landscape)",
+                orientation.trim()
+            )),
+        }
+    }
+}
+
+/// The space left blank around the printed content of each page.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Margins {
+    /// The top margin.
+    pub top: Length,
+    /// The right margin.
+    pub right: Length,
+    /// The bottom margin.
+    pub bottom: Length,
+    /// The left margin.
+    pub left: Length,
+}
+
+impl Default for Margins {
+    /// Chrome's default margins of 0.4 inches (about 1 cm).
+    fn default() -> Self {
+        Margins::uniform(Length::inches(0.4))
+    }
+}
+
+impl Margins {
+    /// Creates margins that are the same on every side.
+    pub const fn uniform(margin: Length) -> Self {
+        Margins { top: margin, right: margin, bottom: margin, left: margin }
+    }
+}
+
+impl FromStr for Margins {
+    type Err = String;
+
+    /// Reads one to four lengths separated by spaces, in the same order
+    /// as the CSS `margin` property: `all`, `vertical horizontal`,
+    /// `top horizontal bottom` or `top right bottom left`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pdf_ok::utils::{Length, Margins};
+    ///
+    /// let margins: Margins = "10 0.5in".parse().unwrap();
+    /// assert_eq!(margins.top, Length::mm(10.0));
+    /// assert_eq!(margins.left, Length::inches(0.5));
+    ///
+    /// let error = "10.25 asd.123 20".parse::<Margins>().unwrap_err();
+    /// assert!(error.contains("\"asd.123\""));
+    /// ```
+    fn from_str(margins: &str) -> Result<Self, Self::Err> {
+        let lengths = margins
+            .split_whitespace()
+            .map(str::parse)
+            .collect::<Result<Vec<Length>, String>>()
+            .map_err(|error| {
+                format!("invalid margins \"{}\": {}", margins.trim(), error)
+            })?;
+
+        match lengths[..] {
+            [all] => Ok(Margins::uniform(all)),
+            [vertical, horizontal] => Ok(Margins {
+                top: vertical,
+                right: horizontal,
+                bottom: vertical,
+                left: horizontal,
+            }),
+            [top, horizontal, bottom] => {
+                Ok(Margins { top, right: horizontal, bottom, left: horizontal })
+            }
+            [top, right, bottom, left] => Ok(Margins { top, right, bottom, left }),
+            _ => Err(format!(
+                "invalid margins \"{}\" (expected one to four lengths)",
+                margins.trim()
+            )),
+        }
+    }
+}
+
+/// The paper size, orientation and margins a document is printed with.
+///
+/// A `@page` rule with a `size` in the theme takes precedence over the
+/// paper size.
+///
+/// # Examples
+///
+/// ```
+/// use pdf_ok::utils::{
+///     parse_front_matter, FrontMatterFormat, PageSetup, PaperOrientation,
+///     PaperSize,
+/// };
+///
+/// let front_matter = parse_front_matter(
+///     "paper_size: A6\norientation: landscape\nmargins: 10mm 5mm",
+///     FrontMatterFormat::Yaml,
+/// )
+/// .unwrap();
+/// let page_setup =
+///     PageSetup::default().with_front_matter(&front_matter).unwrap();
+/// assert_eq!(page_setup.paper_size, PaperSize::A6);
+/// assert_eq!(page_setup.orientation, PaperOrientation::Landscape);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PageSetup {
+    /// The size of the paper.
+    pub paper_size: PaperSize,
+    /// Which way up the paper is printed.
+    pub orientation: PaperOrientation,
+    /// The margins around the content of each page.
+    pub margins: Margins,
+}
+
+impl PageSetup {
+    /// Returns a copy of the page setup with the `paper_size`,
+    /// `orientation` and `margins` front matter values of a document
+    /// applied.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the problem if one of the values is not
+    /// valid.
+    pub fn with_front_matter(
+        &self,
+        front_matter: &FrontMatter,
+    ) -> Result<PageSetup, String> {
+        let mut page_setup = *self;
+        let value = |key: &str| {
+            front_matter.get(key).and_then(FrontMatterValue::as_str)
+        };
+
+        if let Some(paper_size) = value("paper_size") {
+            page_setup.paper_size = paper_size.parse()?;
+        }
+        if let Some(orientation) = value("orientation") {
+            page_setup.orientation = orientation.parse()?;
+        }
+        // A number alone is a uniform margin in millimetres
+        match front_matter.get("margins") {
+            Some(FrontMatterValue::String(margins)) => {
+                page_setup.margins = margins.parse()?;
+            }
+            Some(FrontMatterValue::Integer(margin)) if *margin >= 0 => {
+                page_setup.margins =
+                    Margins::uniform(Length::mm(*margin as f64));
+            }
+            Some(FrontMatterValue::Float(margin)) if *margin >= 0.0 => {
+                page_setup.margins = Margins::uniform(Length::mm(*margin));
+            }
+            Some(FrontMatterValue::Null) | None => {}
+            Some(_) => {
+                return Err("invalid margins (expected one to four lengths)"
+                    .to_string())
+            }
+        }
+
+        Ok(page_setup)
+    }
+
+    /// Returns the options headless Chrome prints the page with.
+    pub fn print_options(&self) -> PrintToPdfOptions {
+        let (width, height) = self.paper_size.dimensions();
+        let (width, height) = match self.orientation {
+            PaperOrientation::Portrait => (width, height),
+            PaperOrientation::Landscape => (height, width),
+        };
+
+        PrintToPdfOptions {
+            paper_width: Some(width.to_inches()),
+            paper_height: Some(height.to_inches()),
+            margin_top: Some(self.margins.top.to_inches()),
+            margin_right: Some(self.margins.right.to_inches()),
+            margin_bottom: Some(self.margins.bottom.to_inches()),
+            margin_left: Some(self.margins.left.to_inches()),
+            // Keep the theme backgrounds and let an `@page` rule set the
+            // page size
+            print_background: Some(true),
+            prefer_css_page_size: Some(true),
+            ..PrintToPdfOptions::default()
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use lopdf::{Dictionary, Document, Object as LopdfObject, ObjectId};
+
+use crate::utils::{decode_pdf_text, pdf_text_string};
+
+/// Reads and edits the document information (Info) dictionary of a
+/// [`lopdf::Document`].
+///
+/// The Info dictionary is the one the trailer's `/Info` entry refers to.
+/// Editing a document without one creates it. Values are read and
+/// written as text, encoded with
+/// [`pdf_text_string`](crate::utils::pdf_text_string).
+///
+/// # Examples
+///
+/// ```
+/// use lopdf::Document;
+///
+/// use pdf_ok::utils::DocumentMetadata;
+///
+/// let mut doc = Document::with_version("1.7");
+/// assert!(doc.info_dictionary().is_none());
+///
+/// doc.set_metadata("Title", "© 2024 Kaishi");
+/// doc.set_metadata("Author", "Łukasz");
+/// assert_eq!(doc.metadata("Title").as_deref(), Some("© 2024 Kaishi"));
+///
+/// assert_eq!(doc.remove_metadata("Author").as_deref(), Some("Łukasz"));
+/// assert_eq!(
+///     doc.metadata_entries(),
+///     vec![("Title".to_string(), "© 2024 Kaishi".to_string())]
+/// );
+/// ```
+pub trait DocumentMetadata {
+    /// Returns the Info dictionary, if the document has one.
+    fn info_dictionary(&self) -> Option<&Dictionary>;
+
+    /// Returns the Info dictionary, first creating it and pointing the
+    /// trailer at it if the document has none.
+    fn info_dictionary_mut(&mut self) -> &mut Dictionary;
+
+    /// Returns the value of an Info entry as text, or `None` if it is
+    /// missing or is not a string or name.
+    fn metadata(&self, key: &str) -> Option<String>;
+
+    /// Sets an Info entry to a text string.
+    fn set_metadata(&mut self, key: &str, value: &str);
+
+    /// Removes an Info entry, returning its value as text.
+    fn remove_metadata(&mut self, key: &str) -> Option<String>;
+
+    /// Returns the Info entries whose values are strings or names, as
+    /// text, in the order they appear.
+    fn metadata_entries(&self) -> Vec<(String, String)>;
+}
+
+impl DocumentMetadata for Document {
+    fn info_dictionary(&self) -> Option<&Dictionary> {
+        match self.trailer.get(b"Info").ok()? {
+            LopdfObject::Reference(id) => self.get_dictionary(*id).ok(),
+            LopdfObject::Dictionary(dictionary) => Some(dictionary),
+            _ => None,
+        }
+    }
+
+    fn info_dictionary_mut(&mut self) -> &mut Dictionary {
+        let id = info_id(self);
+        match self.objects.get_mut(&id) {
+            Some(LopdfObject::Dictionary(dictionary)) => dictionary,
+            _ => unreachable!("info_id always returns a dictionary"),
+        }
+    }
+
+    fn metadata(&self, key: &str) -> Option<String> {
+        self.info_dictionary()?.get(key.as_bytes()).ok().and_then(as_text)
+    }
+
+    fn set_metadata(&mut self, key: &str, value: &str) {
+        self.info_dictionary_mut().set(key, pdf_text_string(value));
+    }
+
+    fn remove_metadata(&mut self, key: &str) -> Option<String> {
+        self.info_dictionary()?;
+        self.info_dictionary_mut()
+            .remove(key.as_bytes())
+            .as_ref()
+            .and_then(as_text)
+    }
+
+    fn metadata_entries(&self) -> Vec<(String, String)> {
+        self.info_dictionary()
+            .map(|dictionary| {
+                dictionary
+                    .iter()
+                    .filter_map(|(key, value)| {
+                        let key = String::from_utf8_lossy(key).to_string();
+                        Some((key, as_text(value)?))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+// Returns the id of the Info dictionary, moving a direct dictionary into
+// an object of its own, or creating an empty one if the trailer has none
+// or refers to something else.
+fn info_id(doc: &mut Document) -> ObjectId {
+    let existing = match doc.trailer.get(b"Info") {
+        Ok(LopdfObject::Reference(id)) => match doc.objects.get(id) {
+            Some(LopdfObject::Dictionary(_)) => return *id,
+            _ => Dictionary::new(),
+        },
+        Ok(LopdfObject::Dictionary(dictionary)) => dictionary.clone(),
+        _ => Dictionary::new(),
+    };
+
+    let id = doc.add_object(existing);
+    doc.trailer.set("Info", id);
+    id
+}
+
+// Reads an Info value as text: strings are decoded, names are written
+// without their slash.
+fn as_text(value: &LopdfObject) -> Option<String> {
+    match value {
+        LopdfObject::String(bytes, _) => Some(decode_pdf_text(bytes)),
+        LopdfObject::Name(name) => {
+            Some(String::from_utf8_lossy(name).to_string())
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,121 @@
+use lopdf::{Object as LopdfObject, StringFormat};
+
+// The characters PDFDocEncoding maps to the bytes 0x18 to 0x1F
+const PDF_DOC_0X18: [char; 8] = [
+    '\u{02D8}', '\u{02C7}', '\u{02C6}', '\u{02D9}', '\u{02DD}', '\u{02DB}',
+    '\u{02DA}', '\u{02DC}',
+];
+
+// The characters PDFDocEncoding maps to the bytes 0x80 to 0x9E
+const PDF_DOC_0X80: [char; 31] = [
+    '\u{2022}', '\u{2020}', '\u{2021}', '\u{2026}', '\u{2014}', '\u{2013}',
+    '\u{0192}', '\u{2044}', '\u{2039}', '\u{203A}', '\u{2212}', '\u{2030}',
+    '\u{201E}', '\u{201C}', '\u{201D}', '\u{2018}', '\u{2019}', '\u{201A}',
+    '\u{2122}', '\u{FB01}', '\u{FB02}', '\u{0141}', '\u{0152}', '\u{0160}',
+    '\u{0178}', '\u{017D}', '\u{0131}', '\u{0142}', '\u{0153}', '\u{0161}',
+    '\u{017E}',
+];
+
+// The byte order mark that starts a UTF-16BE text string
+const UTF_16BE_BOM: [u8; 2] = [0xFE, 0xFF];
+
+/// Encodes text as the bytes of a PDF text string.
+///
+/// Text that PDFDocEncoding can represent is written in it, one byte per
+/// character; anything else is written as UTF-16BE after a byte order
+/// mark, as the PDF specification requires for text strings such as the
+/// values of the document information dictionary.
+///
+/// # Examples
+///
+/// ```
+/// use pdf_ok::utils::encode_pdf_text;
+///
+/// assert_eq!(encode_pdf_text("© 2024 Kaishi")[0], 0xA9);
+/// assert_eq!(encode_pdf_text("Kaishi “Features”")[7], 0x8D);
+/// assert_eq!(encode_pdf_text("開始"), vec![0xFE, 0xFF, 0x95, 0x8B, 0x59, 0xCB]);
+/// ```
+pub fn encode_pdf_text(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(pdf_doc_byte)
+        .collect::<Option<Vec<u8>>>()
+        .unwrap_or_else(|| {
+            UTF_16BE_BOM
+                .into_iter()
+                .chain(text.encode_utf16().flat_map(u16::to_be_bytes))
+                .collect()
+        })
+}
+
+/// Decodes the bytes of a PDF text string, written in UTF-16BE after a
+/// byte order mark or in PDFDocEncoding. Bytes PDFDocEncoding leaves
+/// undefined, and invalid UTF-16, become `U+FFFD`.
+///
+/// # Examples
+///
+/// ```
+/// use pdf_ok::utils::{decode_pdf_text, encode_pdf_text};
+///
+/// for text in ["Kaishi", "© 2024 Kaishi — “Features”", "Łukasz Żółć", "開始"] {
+///     assert_eq!(decode_pdf_text(&encode_pdf_text(text)), text);
+/// }
+/// ```
+pub fn decode_pdf_text(bytes: &[u8]) -> String {
+    match bytes.strip_prefix(&UTF_16BE_BOM) {
+        Some(utf16) => {
+            let units: Vec<u16> = utf16
+                .chunks(2)
+                .map(|pair| match pair {
+                    [high, low] => u16::from_be_bytes([*high, *low]),
+                    _ => 0xFFFD,
+                })
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        None => bytes.iter().map(|byte| pdf_doc_char(*byte)).collect(),
+    }
+}
+
+/// Returns a PDF string object holding text, encoded with
+/// [`encode_pdf_text`]. UTF-16 text is written as a hexadecimal string.
+pub fn pdf_text_string(text: &str) -> LopdfObject {
+    let bytes = encode_pdf_text(text);
+    let format = if bytes.starts_with(&UTF_16BE_BOM) {
+        StringFormat::Hexadecimal
+    } else {
+        StringFormat::Literal
+    };
+
+    LopdfObject::String(bytes, format)
+}
+
+// Returns the PDFDocEncoding byte of a character, if it has one.
+fn pdf_doc_byte(character: char) -> Option<u8> {
+    match character {
+        '\t' | '\n' | '\r' | ' '..='~' => Some(character as u8),
+        '\u{A1}'..='\u{FF}' if character != '\u{AD}' => Some(character as u8),
+        '\u{20AC}' => Some(0xA0),
+        _ => PDF_DOC_0X18
+            .iter()
+            .position(|mapped| *mapped == character)
+            .map(|index| 0x18 + index as u8)
+            .or_else(|| {
+                PDF_DOC_0X80
+                    .iter()
+                    .position(|mapped| *mapped == character)
+                    .map(|index| 0x80 + index as u8)
+            }),
+    }
+}
+
+// Returns the character a PDFDocEncoding byte stands for.
+fn pdf_doc_char(byte: u8) -> char {
+    match byte {
+        0x09 | 0x0A | 0x0D | 0x20..=0x7E => byte as char,
+        0x18..=0x1F => PDF_DOC_0X18[usize::from(byte - 0x18)],
+        0x80..=0x9E => PDF_DOC_0X80[usize::from(byte - 0x80)],
+        0xA0 => '\u{20AC}',
+        0xA1..=0xFF if byte != 0xAD => byte as char,
+        _ => char::REPLACEMENT_CHARACTER,
+    }
+}
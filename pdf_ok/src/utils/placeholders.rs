@@ -0,0 +1,537 @@
+use colored::Colorize;
+use regex::{Captures, Regex};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::utils::{format_date, parse_date, FrontMatter, FrontMatterValue};
+
+/// Options that control how front matter values are written into the
+/// Markdown content.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateOptions {
+    /// The text placed between the items of a list, e.g. `tags: [a, b]`
+    /// becomes `a, b` with the default `", "`.
+    pub list_separator: String,
+    /// What happens to a placeholder whose key is not in the front
+    /// matter.
+    pub missing_key_policy: MissingKeyPolicy,
+}
+
+impl Default for TemplateOptions {
+    fn default() -> Self {
+        TemplateOptions {
+            list_separator: ", ".to_string(),
+            missing_key_policy: MissingKeyPolicy::default(),
+        }
+    }
+}
+
+/// What to do with a placeholder whose key is not in the front matter.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MissingKeyPolicy {
+    /// Leave the placeholder in the output as it is.
+    #[default]
+    Keep,
+    /// Replace the placeholder with nothing.
+    Empty,
+    /// Leave the placeholder in the output and report a warning.
+    Warn,
+    /// Fail the document with an error naming the line.
+    Fail,
+}
+
+impl FromStr for MissingKeyPolicy {
+    type Err = String;
+
+    fn from_str(policy: &str) -> Result<Self, Self::Err> {
+        match policy.to_lowercase().as_str() {
+            "keep" => Ok(MissingKeyPolicy::Keep),
+            "empty" => Ok(MissingKeyPolicy::Empty),
+            "warn" => Ok(MissingKeyPolicy::Warn),
+            "fail" => Ok(MissingKeyPolicy::Fail),
+            _ => Err(format!(
+                "unknown missing key policy \"{}\" (expected keep, empty, warn or fail)",
+                policy
+            )),
+        }
+    }
+}
+
+/// A problem found in a placeholder, with the line it is on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateError {
+    /// The 1-based line number, counted from the start of the content
+    /// that was merged.
+    pub line: usize,
+    /// A description of the problem.
+    pub message: String,
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Markdown content with its placeholders replaced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergedMarkdown {
+    /// The merged Markdown content.
+    pub content: String,
+    /// Placeholders reported under [`MissingKeyPolicy::Warn`].
+    pub warnings: Vec<TemplateError>,
+}
+
+/// Replaces every `{{placeholder}}` in the Markdown content with the
+/// front matter value it refers to.
+///
+/// A placeholder is a path into the front matter: keys are separated by
+/// dots and list items are selected with `[index]` (or `.index`), e.g.
+/// `{{author.name}}`, `{{tags[0]}}` or `{{authors[1].email}}`. Spaces
+/// around the path are ignored. A top-level key that itself contains a
+/// dot is matched as a whole before the path is split.
+///
+/// The value can be passed through filters, separated by `|`:
+///
+/// * `upper`, `lower`, `trim` and `capitalize` change the text.
+/// * `escape` escapes `&`, `<`, `>`, `"` and `'` for use in HTML.
+/// * `default("text")` is used when the key is missing, null or empty.
+/// * `date("%d %B %Y")` reformats a date (see
+///   [`parse_date`](crate::utils::parse_date) for the formats read).
+/// * `join("separator")` joins a list with a different separator.
+///
+/// Values are written with [`format_value`]. Placeholders that do not
+/// resolve to a value are handled by
+/// [`TemplateOptions::missing_key_policy`].
+///
+/// # Arguments
+///
+/// * `front_matter` - The front matter values.
+/// * `markdown_content` - The Markdown content containing placeholders.
+/// * `options` - How values are formatted and missing keys handled.
+///
+/// # Errors
+///
+/// Returns a [`TemplateError`] for an unknown or malformed filter, a
+/// value the `date` filter cannot read, or a missing key under
+/// [`MissingKeyPolicy::Fail`].
+///
+/// # Examples
+///
+/// ```
+/// use pdf_ok::utils::{
+///     merge_markdown_yaml, parse_front_matter, FrontMatterFormat,
+///     MissingKeyPolicy, TemplateOptions,
+/// };
+///
+/// let front_matter = parse_front_matter(
+///     "author:\n  name: Jane\ntags: [rust, pdf]\ndate: July 12, 2023",
+///     FrontMatterFormat::Yaml,
+/// )
+/// .unwrap();
+/// let markdown = "{{author.name | upper}} on {{tags}} ({{tags[0]}})\n\
+///     {{date | date(\"%d %B %Y\")}} {{subtitle | default(\"n/a\")}} {{bob}}";
+/// let merged =
+///     merge_markdown_yaml(&front_matter, markdown, &TemplateOptions::default())
+///         .unwrap();
+/// assert_eq!(merged.content, "JANE on rust, pdf (rust)\n12 July 2023 n/a {{bob}}");
+///
+/// let options = TemplateOptions {
+///     missing_key_policy: MissingKeyPolicy::Fail,
+///     ..TemplateOptions::default()
+/// };
+/// let error = merge_markdown_yaml(&front_matter, markdown, &options).unwrap_err();
+/// assert_eq!(error.to_string(), "line 2: unknown key `bob`");
+/// ```
+pub fn merge_markdown_yaml(
+    front_matter: &FrontMatter,
+    markdown_content: &str,
+    options: &TemplateOptions,
+) -> Result<MergedMarkdown, TemplateError> {
+    let mut warnings: Vec<TemplateError> = Vec::new();
+    let mut first_error: Option<TemplateError> = None;
+
+    let replaced_string = placeholder_regex().replace_all(
+        markdown_content,
+        |captures: &Captures<'_>| {
+            let line = line_number(markdown_content, captures.get(0).unwrap().start());
+            match render_placeholder(front_matter, &captures[1], options) {
+                Ok(Some(replacement_value)) => replacement_value,
+                // If no match found, apply the missing key policy
+                Ok(None) => {
+                    let placeholder = captures[0].to_string();
+                    let message = format!(
+                        "unknown key `{}`",
+                        split_filters(&captures[1])[0].trim()
+                    );
+                    match options.missing_key_policy {
+                        MissingKeyPolicy::Keep => placeholder,
+                        MissingKeyPolicy::Empty => String::new(),
+                        MissingKeyPolicy::Warn => {
+                            warnings.push(TemplateError { line, message });
+                            placeholder
+                        }
+                        MissingKeyPolicy::Fail => {
+                            first_error
+                                .get_or_insert(TemplateError { line, message });
+                            placeholder
+                        }
+                    }
+                }
+                Err(message) => {
+                    first_error.get_or_insert(TemplateError { line, message });
+                    captures[0].to_string()
+                }
+            }
+        },
+    );
+
+    match first_error {
+        Some(error) => Err(error),
+        None => Ok(MergedMarkdown { content: replaced_string.to_string(), warnings }),
+    }
+}
+
+/// Returns the `{{placeholder}}` occurrences in the Markdown content that
+/// do not resolve to a front matter value.
+///
+/// # Examples
+///
+/// ```
+/// use pdf_ok::utils::{
+///     parse_front_matter, unresolved_placeholders, FrontMatterFormat,
+/// };
+///
+/// let front_matter =
+///     parse_front_matter("title: Example", FrontMatterFormat::Yaml).unwrap();
+/// let unresolved = unresolved_placeholders(&front_matter, "{{title}} {{bob}}");
+/// assert_eq!(unresolved, vec!["{{bob}}".to_string()]);
+/// ```
+pub fn unresolved_placeholders(
+    front_matter: &FrontMatter,
+    markdown_content: &str,
+) -> Vec<String> {
+    placeholder_regex()
+        .captures_iter(markdown_content)
+        .filter(|captures| {
+            let path = split_filters(&captures[1])[0];
+            resolve_path(front_matter, path).is_none()
+        })
+        .map(|captures| captures[0].to_string())
+        .collect()
+}
+
+/// Looks up a dotted and indexed path, such as `author.name` or
+/// `tags[0]`, in the front matter.
+///
+/// # Returns
+///
+/// The value at the path, or `None` if any part of the path is missing
+/// or malformed.
+///
+/// # Examples
+///
+/// ```
+/// use pdf_ok::utils::{
+///     parse_front_matter, resolve_path, FrontMatterFormat, FrontMatterValue,
+/// };
+///
+/// let front_matter =
+///     parse_front_matter("authors:\n  - name: Jane", FrontMatterFormat::Yaml)
+///         .unwrap();
+/// assert_eq!(
+///     resolve_path(&front_matter, "authors[0].name"),
+///     Some(&FrontMatterValue::String("Jane".to_string()))
+/// );
+/// assert_eq!(resolve_path(&front_matter, "authors[1].name"), None);
+/// ```
+pub fn resolve_path<'a>(
+    front_matter: &'a FrontMatter,
+    path: &str,
+) -> Option<&'a FrontMatterValue> {
+    let path = path.trim();
+    if let Some(value) = front_matter.get(path) {
+        return Some(value);
+    }
+
+    let segments = parse_path(path)?;
+    let (first, rest) = segments.split_first()?;
+    let PathSegment::Key(first_key) = first else {
+        return None;
+    };
+
+    let mut value = front_matter.get(*first_key)?;
+    for segment in rest {
+        value = match (segment, value) {
+            (PathSegment::Key(key), FrontMatterValue::Mapping(mapping)) => {
+                mapping.get(*key)?
+            }
+            // A numeric key such as `tags.0` also indexes into a list
+            (PathSegment::Key(key), FrontMatterValue::Sequence(sequence)) => {
+                sequence.get(key.parse::<usize>().ok()?)?
+            }
+            (PathSegment::Index(index), FrontMatterValue::Sequence(sequence)) => {
+                sequence.get(*index)?
+            }
+            _ => return None,
+        };
+    }
+
+    Some(value)
+}
+
+/// Formats a front matter value as text.
+///
+/// Strings are written as they are, numbers and booleans in their usual
+/// form (whole floats without a trailing `.0`) and null as nothing.
+/// Lists are joined with [`TemplateOptions::list_separator`]. Nested
+/// mappings are written as `key: value` pairs joined the same way.
+///
+/// # Examples
+///
+/// ```
+/// use pdf_ok::utils::{format_value, FrontMatterValue, TemplateOptions};
+///
+/// let options = TemplateOptions {
+///     list_separator: " | ".to_string(),
+///     ..TemplateOptions::default()
+/// };
+/// let tags = FrontMatterValue::Sequence(vec![
+///     FrontMatterValue::String("rust".to_string()),
+///     FrontMatterValue::Float(2.0),
+///     FrontMatterValue::Bool(true),
+/// ]);
+/// assert_eq!(format_value(&tags, &options), "rust | 2 | true");
+/// ```
+pub fn format_value(value: &FrontMatterValue, options: &TemplateOptions) -> String {
+    match value {
+        FrontMatterValue::Null => String::new(),
+        FrontMatterValue::Bool(bool_value) => bool_value.to_string(),
+        FrontMatterValue::Integer(integer) => integer.to_string(),
+        FrontMatterValue::Float(float) => float.to_string(),
+        FrontMatterValue::String(string_value) => string_value.clone(),
+        FrontMatterValue::Sequence(sequence) => sequence
+            .iter()
+            .map(|item| format_value(item, options))
+            .collect::<Vec<_>>()
+            .join(&options.list_separator),
+        FrontMatterValue::Mapping(mapping) => mapping
+            .iter()
+            .map(|(key, item)| format!("{}: {}", key, format_value(item, options)))
+            .collect::<Vec<_>>()
+            .join(&options.list_separator),
+    }
+}
+
+/// Prints the merged Markdown content, ready for PDF processing.
+pub(crate) fn print_merged_markdown(merged_markdown: &str) {
+    println!(
+        "\n{}{}",
+        "Markdown with replaced YAML values. Ready for PDF processing"
+            .bright_green(),
+        merged_markdown
+    );
+}
+
+// Evaluates the contents of a placeholder: a path followed by optional
+// filters. Returns Ok(None) if the path does not resolve and no
+// `default` filter supplied a value.
+fn render_placeholder(
+    front_matter: &FrontMatter,
+    placeholder: &str,
+    options: &TemplateOptions,
+) -> Result<Option<String>, String> {
+    let parts = split_filters(placeholder);
+    let mut value: Option<FrontMatterValue> =
+        resolve_path(front_matter, parts[0]).cloned();
+
+    for filter in &parts[1..] {
+        let (name, argument) = parse_filter(filter)?;
+        value = apply_filter(name, argument.as_deref(), value, options)?;
+    }
+
+    Ok(value.map(|value| format_value(&value, options)))
+}
+
+// Applies a single named filter to a value.
+fn apply_filter(
+    name: &str,
+    argument: Option<&str>,
+    value: Option<FrontMatterValue>,
+    options: &TemplateOptions,
+) -> Result<Option<FrontMatterValue>, String> {
+    let takes_argument = matches!(name, "default" | "date" | "join");
+    match (takes_argument, argument) {
+        (true, None) => {
+            return Err(format!("filter `{}` needs an argument", name));
+        }
+        (false, Some(_)) => {
+            return Err(format!("filter `{}` does not take an argument", name));
+        }
+        _ => {}
+    }
+
+    if name == "default" {
+        let is_empty = match &value {
+            None | Some(FrontMatterValue::Null) => true,
+            Some(FrontMatterValue::String(string_value)) => string_value.is_empty(),
+            _ => false,
+        };
+        return Ok(if is_empty {
+            argument.map(|text| FrontMatterValue::String(text.to_string()))
+        } else {
+            value
+        });
+    }
+
+    // Every other filter passes a missing value through untouched
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    let text = || format_value(&value, options);
+
+    let filtered = match name {
+        "upper" => text().to_uppercase(),
+        "lower" => text().to_lowercase(),
+        "trim" => text().trim().to_string(),
+        "escape" => escape_html(&text()),
+        "capitalize" => {
+            let text = text();
+            let mut characters = text.chars();
+            match characters.next() {
+                Some(first) => first.to_uppercase().chain(characters).collect(),
+                None => String::new(),
+            }
+        }
+        "date" => {
+            let text = text();
+            let date = parse_date(&text)
+                .ok_or_else(|| format!("cannot read \"{}\" as a date", text))?;
+            format_date(&date, argument.unwrap_or_default())?
+        }
+        "join" => {
+            let join_options = TemplateOptions {
+                list_separator: argument.unwrap_or_default().to_string(),
+                ..options.clone()
+            };
+            format_value(&value, &join_options)
+        }
+        _ => return Err(format!("unknown filter `{}`", name)),
+    };
+
+    Ok(Some(FrontMatterValue::String(filtered)))
+}
+
+// Escapes the characters that have a special meaning in HTML text and
+// attribute values.
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(character),
+        }
+    }
+    escaped
+}
+
+// Splits a placeholder on the `|` characters that are not inside quotes.
+// The first part is the path; the rest are filters.
+fn split_filters(placeholder: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut quote: Option<char> = None;
+    let mut part_start = 0;
+
+    for (index, character) in placeholder.char_indices() {
+        match (quote, character) {
+            (Some(open), _) if character == open => quote = None,
+            (None, '"' | '\'') => quote = Some(character),
+            (None, '|') => {
+                parts.push(placeholder[part_start..index].trim());
+                part_start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(placeholder[part_start..].trim());
+
+    parts
+}
+
+// Splits `name("argument")` into its name and unquoted argument.
+fn parse_filter(filter: &str) -> Result<(&str, Option<String>), String> {
+    let Some(open) = filter.find('(') else {
+        if filter.is_empty() {
+            return Err("empty filter".to_string());
+        }
+        return Ok((filter, None));
+    };
+
+    let name = filter[..open].trim();
+    let argument = filter[open + 1..]
+        .strip_suffix(')')
+        .map(str::trim)
+        .ok_or_else(|| format!("filter `{}` is missing a closing `)`", name))?;
+    let unquoted = ['"', '\'']
+        .iter()
+        .find_map(|quote| argument.strip_prefix(*quote)?.strip_suffix(*quote))
+        .ok_or_else(|| {
+            format!("the argument of filter `{}` must be a quoted string", name)
+        })?;
+
+    Ok((name, Some(unquoted.to_string())))
+}
+
+// One part of a placeholder path.
+#[derive(Debug, PartialEq)]
+enum PathSegment<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+// Splits `authors[0].name` into its key and index segments. Returns None
+// for malformed paths, such as empty keys or unclosed brackets.
+fn parse_path(path: &str) -> Option<Vec<PathSegment<'_>>> {
+    let mut segments = Vec::new();
+
+    for part in path.split('.') {
+        let (key, mut indexes) = match part.find('[') {
+            Some(bracket) => (&part[..bracket], &part[bracket..]),
+            None => (part, ""),
+        };
+        if !key.is_empty() {
+            if key.contains(char::is_whitespace) || key.contains(']') {
+                return None;
+            }
+            segments.push(PathSegment::Key(key));
+        } else if indexes.is_empty() || segments.is_empty() {
+            return None;
+        }
+
+        while !indexes.is_empty() {
+            let close = indexes.find(']')?;
+            let index = indexes[1..close].trim().parse::<usize>().ok()?;
+            segments.push(PathSegment::Index(index));
+            indexes = &indexes[close + 1..];
+            if !indexes.is_empty() && !indexes.starts_with('[') {
+                return None;
+            }
+        }
+    }
+
+    Some(segments)
+}
+
+// Returns the 1-based line number of a byte offset in the content.
+fn line_number(content: &str, offset: usize) -> usize {
+    content[..offset].matches('\n').count() + 1
+}
+
+// Matches `{{...}}` placeholders, capturing their contents.
+fn placeholder_regex() -> Regex {
+    Regex::new(r"\{\{([^}]*)\}\}").unwrap()
+}
@@ -1,202 +1,437 @@
 use colored::*;
-use regex::Regex;
-use serde_yaml::Value;
-use std::collections::BTreeMap;
-use std::fs::File;
-use std::io::{self, BufRead};
-use std::path::Path;
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
 
-use crate::utils::generate_pdf;
+use crate::utils::placeholders::print_merged_markdown;
+use crate::utils::{
+    apply_layout, content_hash, generate_pdf, inline_theme, output_path,
+    parse_front_matter, render_markdown, render_markdown_template,
+    select_layout, select_theme, split_front_matter, BuildCache, BuildConfig,
+    FrontMatter, Layout, MissingKeyPolicy, PageSetup, Renderer,
+    TemplateOptions, Theme, BUILD_CACHE_FILE_NAME,
+};
+use crate::Error;
 
-/// Reads data from Markdown files, extracts YAML front matter, and generates PDF files.
+/// Reads data from Markdown files, extracts YAML, TOML or JSON front matter, and generates PDF files.
+///
+/// Up to [`BuildConfig::jobs`] files are processed at the same time,
+/// sharing one headless Chrome instance with at most
+/// [`BuildConfig::max_tabs`] tabs open, which is closed once they are
+/// done. A file that fails does not stop the batch; its error is
+/// recorded and the other files are processed. If two files would write
+/// the same PDF, the later one in `files` fails instead.
+///
+/// A file is skipped if its front matter, body, layout, theme and the
+/// render options are the same as when its PDF was last generated, as
+/// recorded in the build cache in the output directory, unless
+/// [`BuildConfig::force`] is set.
 ///
 /// # Arguments
 ///
-/// * `files` - A vector of file paths to Markdown files containing Front Mattter YAML.
+/// * `files` - A vector of file paths to Markdown files containing Front Mattter YAML,
+///   typically from [`discover_source_files`](crate::utils::discover_source_files).
+/// * `config` - The settings shared by every document in the build.
+///
+/// # Returns
+///
+/// One result per input file, in the same order as `files`: the path of
+/// the generated PDF, or the [`Error`] that stopped that file.
 ///
 /// # Examples
 ///
-/// ```
-/// use your_crate_name::read_file_data;
+/// ```no_run
+/// use pdf_ok::utils::{discover_source_files, read_file_data, BuildConfig};
 ///
-/// let files = vec!["./source_files/404.md"];
-/// read_file_data(files);
+/// let files = discover_source_files(&["docs"], &["**/*.md"], &[]).unwrap();
+/// for result in read_file_data(files, &BuildConfig::default()) {
+///     if let Err(error) = result {
+///         eprintln!("{}", error);
+///     }
+/// }
 /// ```
-pub fn read_file_data(files: Vec<&str>) {
-    let mut file = 0;
-    let mut yaml_delimiter_count = 0;
-    let mut yaml_content: String = String::default();
-    let mut markdown_content: String = String::default();
-
-    while file < files.len() {
-        let filename = files[file];
-        if let Ok(lines) = read_lines(filename) {
-            println!("{}", filename.bright_yellow());
-            // Consumes the iterator, returns an (Optional) String
-            for line in lines.map_while(Result::ok) {
-                if line.trim() == "---" {
-                    yaml_delimiter_count += 1;
-                }
-
-                if yaml_delimiter_count == 1
-                    && line.trim() != "---"
-                    && yaml_delimiter_count < 2
-                {
-                    yaml_content
-                        .push_str(&format!("{}{}", &line, "\n"));
-                }
-
-                if yaml_delimiter_count == 2 && line.trim() != "---" {
-                    markdown_content
-                        .push_str(&format!("{}{}", &line, "\n"));
-                }
+pub fn read_file_data(
+    files: Vec<PathBuf>,
+    config: &BuildConfig,
+) -> Vec<Result<PathBuf, Error>> {
+    let renderer = Renderer::with_max_tabs(config.max_tabs);
+    let cache_path =
+        config.output_options.directory.join(BUILD_CACHE_FILE_NAME);
+    let cache = Mutex::new(BuildCache::load(&cache_path));
+
+    let process_files = || {
+        // Work out every output path first, so clashes are found in
+        // input order rather than in the order files finish
+        let mut prepared: Vec<Result<PreparedFile, Error>> = files
+            .par_iter()
+            .map(|filename| prepare_file(filename, config))
+            .collect();
+        reject_duplicate_outputs(&files, &mut prepared);
+
+        files
+            .par_iter()
+            .zip(prepared)
+            .map(|(filename, prepared)| {
+                println!("{}", filename.display().to_string().bright_yellow());
+                process_file(&renderer, &cache, filename, prepared?, config)
+            })
+            .collect()
+    };
+
+    // Fall back to the global thread pool if a sized one cannot be built
+    let pool = ThreadPoolBuilder::new().num_threads(config.jobs.max(1)).build();
+    let results = match pool {
+        Ok(pool) => pool.install(process_files),
+        Err(_) => process_files(),
+    };
+
+    renderer.shutdown();
+    let cache = cache.into_inner().unwrap_or_else(PoisonError::into_inner);
+    if let Err(error) = cache.save(&cache_path) {
+        println!(
+            "{} could not save the build cache: {}",
+            "warning".bright_yellow(),
+            error
+        );
+    }
+    results
+}
+
+// A source file whose front matter has been parsed and output path
+// worked out, ready to be rendered.
+struct PreparedFile {
+    source: SourceFile,
+    pdf_path: PathBuf,
+}
+
+// Parses a source file and works out where its PDF is written.
+fn prepare_file(
+    filename: &Path,
+    config: &BuildConfig,
+) -> Result<PreparedFile, Error> {
+    let source = parse_source_file(filename)?;
+    let pdf_path =
+        output_path(filename, &source.front_matter, &config.output_options)?;
+
+    Ok(PreparedFile { source, pdf_path })
+}
+
+// Fails every file that would write the same PDF as an earlier file.
+fn reject_duplicate_outputs(
+    files: &[PathBuf],
+    prepared: &mut [Result<PreparedFile, Error>],
+) {
+    let mut first_sources: HashMap<PathBuf, &Path> = HashMap::new();
+
+    for (filename, result) in files.iter().zip(prepared.iter_mut()) {
+        let Ok(prepared_file) = result else { continue };
+        match first_sources.get(&prepared_file.pdf_path) {
+            Some(first_source) => {
+                *result = Err(Error::OutputPath {
+                    path: filename.clone(),
+                    message: format!(
+                        "{} is also written by {}",
+                        prepared_file.pdf_path.display(),
+                        first_source.display()
+                    ),
+                });
+            }
+            None => {
+                first_sources.insert(prepared_file.pdf_path.clone(), filename);
             }
         }
-        yaml_delimiter_count = 0;
-
-        let yaml: Value = serde_yaml::from_str(&yaml_content).unwrap();
-        // Convert Front Matter YAML to a BTreeMap
-        let yaml_btreemap: BTreeMap<String, Value> =
-            yaml_mapping_to_btreemap(&yaml).unwrap();
-        // println!(
-        //     "{} {:#?}\n",
-        //     "yaml_btreemap value:".cyan(),
-        //     yaml_btreemap
-        // );
-        // println!(
-        //     "{} {}",
-        //     "subtitle:".cyan(),
-        //     yaml_to_string(yaml_btreemap.get("subtitle").unwrap())
-        //         .unwrap()
-        //         .trim()
-        //         .bright_yellow()
-        // );
-
-        // Insert Font Matter YAML into markdown (if applicable)
-        // TODO RL Add some sort of boolean check
-        let merged_markdown_yaml =
-            merge_markdown_yaml(yaml_btreemap.clone(), &markdown_content);
-
-        // Convert Markdown content to HTML
-        // markdown:: comes from the markdown crate
-        let html: String =
-            markdown::to_html(&merged_markdown_yaml.to_owned());
-
-        // Remove the markdown, md, file extension
-        let filename_path = filename.trim_end_matches(".md");
-        let _ = generate_pdf(html, filename_path, yaml_btreemap);
-
-        file += 1;
-        // Reset yaml and markdown content ready for the next file
-        yaml_content = String::default();
-        markdown_content = String::default();
     }
 }
 
-// The output is wrapped in a Result to allow matching on errors.
-// Returns an Iterator to the Reader of the lines of the file.
-fn read_lines<P>(
-    filename: P,
-) -> io::Result<io::Lines<io::BufReader<File>>>
-where
-    P: AsRef<Path>,
-{
-    let file = File::open(filename)?;
-    Ok(io::BufReader::new(file).lines())
+// Turns a single prepared Markdown file into a PDF, unless the build
+// cache shows its PDF is up to date.
+fn process_file(
+    renderer: &Renderer,
+    cache: &Mutex<BuildCache>,
+    filename: &Path,
+    prepared: PreparedFile,
+    config: &BuildConfig,
+) -> Result<PathBuf, Error> {
+    let PreparedFile { source, pdf_path } = prepared;
+
+    // Everything besides the source file that the PDF depends on
+    let style = DocumentStyle {
+        layout: select_layout(&source.front_matter, &config.layouts_directory)?,
+        theme: select_theme(
+            &source.front_matter,
+            &config.theme,
+            &config.themes_directory,
+            filename,
+        )?,
+        page_setup: config
+            .page_setup
+            .with_front_matter(&source.front_matter)
+            .map_err(|message| Error::PageSetup {
+                path: filename.to_path_buf(),
+                message,
+            })?,
+    };
+
+    let hash = document_hash(&source, &style, config);
+    let lock_cache = || cache.lock().unwrap_or_else(PoisonError::into_inner);
+    if !config.force && lock_cache().is_up_to_date(filename, &hash, &pdf_path) {
+        println!("{} {}", "up to date".bright_green(), pdf_path.display());
+        return Ok(pdf_path);
+    }
+
+    let rendered =
+        render_file(renderer, filename, source, &style, &pdf_path, config);
+    match &rendered {
+        Ok(pdf_path) => lock_cache().record(filename, &hash, pdf_path),
+        Err(_) => lock_cache().remove(filename),
+    }
+    rendered
 }
 
-/// Converts a YAML mapping into a Rust BTreeMap with string keys and arbitrary values. BTreeMaps are automatically alphabetically sorted.
+// The layout, theme and page setup a source file is printed with.
+struct DocumentStyle {
+    layout: Layout,
+    theme: Theme,
+    page_setup: PageSetup,
+}
+
+// Hashes everything a PDF is generated from: the front matter and body,
+// the layout and theme stylesheet (with its fonts), and the options.
+fn document_hash(
+    source: &SourceFile,
+    style: &DocumentStyle,
+    config: &BuildConfig,
+) -> String {
+    let options = format!(
+        "{:?} {:?} {:?} {:?}",
+        config.template_options,
+        config.markdown_options,
+        style.page_setup,
+        config.metadata_options
+    );
+
+    content_hash(&[
+        env!("CARGO_PKG_VERSION").as_bytes(),
+        format!("{:?}", source.front_matter).as_bytes(),
+        source.body.as_bytes(),
+        style.layout.source.as_bytes(),
+        style.theme.stylesheet.as_bytes(),
+        options.as_bytes(),
+    ])
+}
+
+// Renders a source file with its layout and theme, and prints it to a
+// PDF.
+fn render_file(
+    renderer: &Renderer,
+    filename: &Path,
+    source: SourceFile,
+    style: &DocumentStyle,
+    pdf_path: &Path,
+    config: &BuildConfig,
+) -> Result<PathBuf, Error> {
+    // Evaluate the blocks and insert Font Matter values into markdown (if applicable)
+    let merged_markdown =
+        render_source_file(filename, &source, &config.template_options)?;
+    print_merged_markdown(&merged_markdown);
+
+    // Convert Markdown content to HTML
+    let body: String =
+        render_markdown(&merged_markdown, &config.markdown_options);
+
+    // Wrap the body in the layout named by the front matter
+    let html = render_layout(
+        filename,
+        &source,
+        &style.layout,
+        &body,
+        &config.template_options,
+    )?;
+
+    // Inline the theme stylesheet so the page is printed with it
+    let html = inline_theme(&html, &style.theme);
+
+    generate_pdf(
+        renderer,
+        html,
+        filename,
+        pdf_path,
+        source.front_matter,
+        &style.page_setup,
+        &config.metadata_options,
+    )
+}
+
+// Wraps the rendered body of a source file in its layout, printing any
+// warnings. Errors are reported against the layout file.
+fn render_layout(
+    filename: &Path,
+    source: &SourceFile,
+    layout: &Layout,
+    body: &str,
+    options: &TemplateOptions,
+) -> Result<String, Error> {
+    let merged = apply_layout(
+        layout,
+        body,
+        &source.front_matter,
+        options,
+    )
+    .map_err(|error| Error::Template {
+        path: layout.display_path(),
+        line: error.line,
+        message: format!("{} (used by {})", error.message, filename.display()),
+    })?;
+
+    for warning in merged.warnings {
+        println!(
+            "{} {}:{}: {}",
+            "warning".bright_yellow(),
+            layout.display_path().display(),
+            warning.line,
+            warning.message
+        );
+    }
+
+    Ok(merged.content)
+}
+
+/// Parses the front matter and checks the block tags and placeholders of
+/// each file, its layout and its output file name, without generating
+/// or writing anything.
+///
+/// Each file is reported on the console. Placeholders whose key is not
+/// in the front matter are always reported as warnings, and fail the
+/// file if the missing key policy is
+/// [`MissingKeyPolicy::Fail`](crate::utils::MissingKeyPolicy::Fail).
 ///
 /// # Arguments
 ///
-/// * `yaml` - A reference to a serde_yaml::Value containing the YAML data to convert.
+/// * `files` - A vector of file paths to Markdown files containing Front Mattter YAML.
+/// * `config` - The settings shared by every document in the build.
 ///
 /// # Returns
 ///
-/// An Option containing the resulting BTreeMap<String, Value>. If the YAML data is successfully
-/// converted into a BTreeMap, it returns Some(btreemap); otherwise, it returns None.
+/// One result per input file, in the same order as `files`: the path of
+/// the file if it could be read and its front matter, placeholders,
+/// layout and output file name checked, otherwise the [`Error`]
+/// describing the problem.
 ///
 /// # Examples
 ///
-/// ```
-/// use serde_yaml::Value;
-/// use std::collections::BTreeMap;
+/// ```no_run
+/// use std::path::PathBuf;
+///
+/// use pdf_ok::utils::{check_file_data, BuildConfig};
 ///
-/// let yaml_data = serde_yaml::from_str("name: John\nage: 30").unwrap();
-/// let btreemap = yaml_mapping_to_btreemap(&yaml_data).unwrap();
-/// assert_eq!(btreemap.get("name"), Some(&Value::String("John".to_string())));
-/// assert_eq!(btreemap.get("age"), Some(&Value::Number(30.into())));
+/// let files = vec![PathBuf::from("./source_files/404.md")];
+/// let results = check_file_data(files, &BuildConfig::default());
+/// assert!(results.iter().all(Result::is_ok));
 /// ```
-fn yaml_mapping_to_btreemap(
-    yaml: &Value,
-) -> Option<BTreeMap<String, Value>> {
-    match yaml {
-        // Match if yaml Value contains a Mapping 'object'
-        Value::Mapping(mapping_value) => {
-            // Create a new BTreeMap to hold the YAML data
-            let mut yaml_btreemap: BTreeMap<String, Value> =
-                BTreeMap::new();
-
-            // Iterate over key-value pairs in the mapping
-            for (key, value) in mapping_value.iter() {
-                // Destructure the key-value tuple, if the key is of type Value::String.
-                if let (Value::String(key), value) = (key, value) {
-                    // Insert key-value pair into the BTreeMap. The key and value values are cloned because or_insert takes ownership of the arguments
-                    yaml_btreemap
-                        .entry(key.clone())
-                        .or_insert(value.clone());
-                } else {
-                    // Handle non-string keys or non-scalar values
-                    return None;
-                }
-            }
+pub fn check_file_data(
+    files: Vec<PathBuf>,
+    config: &BuildConfig,
+) -> Vec<Result<PathBuf, Error>> {
+    let mut results: Vec<Result<PathBuf, Error>> = Vec::new();
+
+    // Missing keys are worth reporting even if a build would keep them
+    let mut template_options = config.template_options.clone();
+    if template_options.missing_key_policy != MissingKeyPolicy::Fail {
+        template_options.missing_key_policy = MissingKeyPolicy::Warn;
+    }
+
+    for filename in files {
+        let display_name = filename.display().to_string();
+        let checked = parse_source_file(&filename).and_then(|source| {
+            let merged_markdown =
+                render_source_file(&filename, &source, &template_options)?;
+            output_path(&filename, &source.front_matter, &config.output_options)?;
 
-            // println!("{} {:#?}", "yaml_btreemap".cyan(), yaml_btreemap);
-            // Return the resulting BTreeMap
-            Some(yaml_btreemap)
+            // Render the layout as a build would, but discard the result
+            let layout =
+                select_layout(&source.front_matter, &config.layouts_directory)?;
+            let body = render_markdown(&merged_markdown, &config.markdown_options);
+            render_layout(&filename, &source, &layout, &body, &template_options)
+        });
+
+        match checked {
+            Ok(_) => {
+                println!("{} {}", "ok".bright_green(), display_name);
+                results.push(Ok(filename));
+            }
+            Err(error) => {
+                println!("{} {}", "error".bright_red(), display_name);
+                results.push(Err(error));
+            }
         }
-        _ => None, // Return None if yaml is not a mapping
     }
+
+    results
 }
 
-fn merge_markdown_yaml(
-    yaml_btreemap: BTreeMap<String, Value>,
-    markdown_content: &str,
-) -> String {
-    // TODO RL Look into if this could be taken out into a utility function
-    let mut new_btreemap: BTreeMap<String, String> = BTreeMap::new();
-    for (key, value) in yaml_btreemap {
-        if let Value::String(string_value) = value {
-            new_btreemap.insert(key, string_value);
+// A source file split into its parsed front matter and Markdown body.
+struct SourceFile {
+    front_matter: FrontMatter,
+    body: String,
+    // The line of the source file the body starts on
+    body_line: usize,
+}
+
+// Reads a source file and parses its front matter.
+fn parse_source_file(filename: &Path) -> Result<SourceFile, Error> {
+    let content = fs::read_to_string(filename)
+        .map_err(|source| Error::Io { path: filename.to_path_buf(), source })?;
+    let split = split_front_matter(&content);
+
+    // A file without front matter simply has no values to use
+    let front_matter = match split.front_matter {
+        Some(front_matter_content) => {
+            parse_front_matter(front_matter_content, split.format).map_err(
+                |message| Error::FrontMatter {
+                    path: filename.to_path_buf(),
+                    message,
+                },
+            )?
         }
-    }
+        None => FrontMatter::new(),
+    };
 
-    let hay = markdown_content;
-    let regex = Regex::new(r"\{\{([^}]*)\}\}").unwrap();
-
-    let replaced_string =
-        regex.replace_all(hay, |captures: &regex::Captures<'_>| {
-            let replacement_key =
-                captures.get(1).map(|m| m.as_str()).unwrap_or("");
-            if let Some(replacement_value) =
-                new_btreemap.get(replacement_key)
-            {
-                replacement_value.clone() // Clone the string to ensure ownership
-            } else {
-                // If no match found, return the original placeholder
-                captures
-                    .get(0)
-                    .map(|m| String::from(m.as_str()))
-                    .unwrap_or_default()
-            }
-        });
+    Ok(SourceFile {
+        front_matter,
+        body: split.body.to_string(),
+        body_line: content[..split.body_offset].matches('\n').count() + 1,
+    })
+}
 
-    println!(
-        "\n{}{}",
-        "Markdown with replaced YAML values. Ready for PDF processing"
-            .bright_green(),
-        replaced_string
-    );
+// Renders the block tags and placeholders in the body of a source file, printing any
+// warnings. Line numbers are reported relative to the whole file.
+fn render_source_file(
+    filename: &Path,
+    source: &SourceFile,
+    options: &TemplateOptions,
+) -> Result<String, Error> {
+    let to_file_line = |line: usize| source.body_line + line - 1;
+
+    let merged =
+        render_markdown_template(&source.front_matter, &source.body, options)
+            .map_err(|error| Error::Template {
+                path: filename.to_path_buf(),
+                line: to_file_line(error.line),
+                message: error.message,
+            })?;
+
+    for warning in merged.warnings {
+        println!(
+            "{} {}:{}: {}",
+            "warning".bright_yellow(),
+            filename.display(),
+            to_file_line(warning.line),
+            warning.message
+        );
+    }
 
-    replaced_string.to_string()
+    Ok(merged.content)
 }
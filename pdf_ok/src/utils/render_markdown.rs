@@ -0,0 +1,122 @@
+/// Options for converting Markdown to HTML.
+///
+/// Rendering follows the CommonMark specification. Each GitHub Flavored
+/// Markdown (GFM) extension can be switched on or off on its own; all of
+/// them are on by default.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkdownOptions {
+    /// GFM tables.
+    pub tables: bool,
+    /// GFM task list items, e.g. `- [x] Done`.
+    pub task_lists: bool,
+    /// GFM strikethrough, e.g. `~~removed~~`.
+    pub strikethrough: bool,
+    /// Footnote references and definitions, e.g. `[^1]`.
+    pub footnotes: bool,
+    /// GFM autolinks for bare URLs and email addresses.
+    pub autolinks: bool,
+    /// Pass raw HTML in the Markdown through to the output. When off, it
+    /// is replaced by an HTML comment.
+    pub raw_html: bool,
+}
+
+impl Default for MarkdownOptions {
+    fn default() -> Self {
+        MarkdownOptions {
+            tables: true,
+            task_lists: true,
+            strikethrough: true,
+            footnotes: true,
+            autolinks: true,
+            raw_html: true,
+        }
+    }
+}
+
+impl MarkdownOptions {
+    /// The names accepted by [`MarkdownOptions::set_extension`].
+    pub const EXTENSION_NAMES: [&'static str; 6] = [
+        "tables",
+        "task-lists",
+        "strikethrough",
+        "footnotes",
+        "autolinks",
+        "raw-html",
+    ];
+
+    /// Switches an extension on or off by name. The names are listed in
+    /// [`MarkdownOptions::EXTENSION_NAMES`].
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the problem if the name is not a known
+    /// extension.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pdf_ok::utils::MarkdownOptions;
+    ///
+    /// let mut options = MarkdownOptions::default();
+    /// options.set_extension("footnotes", false).unwrap();
+    /// assert!(!options.footnotes);
+    /// options.set_extension("raw-html", false).unwrap();
+    /// assert!(!options.raw_html);
+    /// assert!(options.set_extension("emoji", true).is_err());
+    /// ```
+    pub fn set_extension(
+        &mut self,
+        name: &str,
+        enabled: bool,
+    ) -> Result<(), String> {
+        let extension = match name {
+            "tables" => &mut self.tables,
+            "task-lists" => &mut self.task_lists,
+            "strikethrough" => &mut self.strikethrough,
+            "footnotes" => &mut self.footnotes,
+            "autolinks" => &mut self.autolinks,
+            "raw-html" => &mut self.raw_html,
+            _ => {
+                return Err(format!(
+                    "unknown Markdown extension \"{}\" (expected one of: {})",
+                    name,
+                    Self::EXTENSION_NAMES.join(", ")
+                ))
+            }
+        };
+        *extension = enabled;
+
+        Ok(())
+    }
+}
+
+/// Converts Markdown to an HTML fragment.
+///
+/// # Arguments
+///
+/// * `markdown` - The Markdown content.
+/// * `options` - Which extensions are enabled.
+///
+/// # Returns
+///
+/// The rendered HTML.
+///
+/// # Examples
+///
+/// ```
+/// use pdf_ok::utils::{render_markdown, MarkdownOptions};
+///
+/// let html = render_markdown("~~old~~ new", &MarkdownOptions::default());
+/// assert_eq!(html, "<p><del>old</del> new</p>\n");
+/// ```
+pub fn render_markdown(markdown: &str, options: &MarkdownOptions) -> String {
+    let mut comrak_options = comrak::Options::default();
+    comrak_options.extension.table = options.tables;
+    comrak_options.extension.tasklist = options.task_lists;
+    comrak_options.extension.strikethrough = options.strikethrough;
+    comrak_options.extension.footnotes = options.footnotes;
+    comrak_options.extension.autolink = options.autolinks;
+    comrak_options.render.unsafe_ = options.raw_html;
+
+    comrak::markdown_to_html(markdown, &comrak_options)
+}
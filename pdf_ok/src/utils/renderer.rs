@@ -0,0 +1,184 @@
+use colored::Colorize;
+use headless_chrome::{Browser, Tab};
+use std::ops::Deref;
+use std::path::Path;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+
+use crate::Error;
+
+/// The number of tabs a renderer keeps open at once when none is
+/// configured.
+pub const DEFAULT_MAX_TABS: usize = 4;
+
+/// Owns the headless Chrome instance shared by every document in a
+/// build.
+///
+/// Chrome is started when the first tab is requested, rather than once
+/// per document. If it has crashed or stopped responding by the time the
+/// next tab is requested, it is started again. Call
+/// [`Renderer::shutdown`] (or drop the renderer) to close it.
+///
+/// A renderer can be shared between threads. At most
+/// [`max_tabs`](Renderer::with_max_tabs) tabs are open at once; further
+/// requests wait until a tab is closed.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::path::Path;
+///
+/// use pdf_ok::utils::Renderer;
+///
+/// let renderer = Renderer::new();
+/// let tab = renderer.new_tab(Path::new("hello.md")).unwrap();
+/// tab.navigate_to("data:text/html,<h1>Hello</h1>").unwrap();
+/// drop(tab);
+/// renderer.shutdown();
+/// ```
+pub struct Renderer {
+    browser: Mutex<Option<Browser>>,
+    max_tabs: usize,
+    open_tabs: Mutex<usize>,
+    tab_closed: Condvar,
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Renderer::with_max_tabs(DEFAULT_MAX_TABS)
+    }
+}
+
+impl Renderer {
+    /// Creates a renderer that keeps up to [`DEFAULT_MAX_TABS`] tabs
+    /// open. Chrome is not started until the first tab is requested.
+    pub fn new() -> Self {
+        Renderer::default()
+    }
+
+    /// Creates a renderer that keeps up to `max_tabs` tabs open at once
+    /// (at least one).
+    pub fn with_max_tabs(max_tabs: usize) -> Self {
+        Renderer {
+            browser: Mutex::new(None),
+            max_tabs: max_tabs.max(1),
+            open_tabs: Mutex::new(0),
+            tab_closed: Condvar::new(),
+        }
+    }
+
+    /// Opens a new tab for rendering a single document, waiting for
+    /// another tab to close if the maximum are already open. The tab is
+    /// closed when the returned [`RendererTab`] is dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error::BrowserLaunch`] for `source_path`, the
+    /// document the tab is for, if Chrome cannot be started, or cannot
+    /// open a tab even after being restarted.
+    pub fn new_tab(
+        &self,
+        source_path: &Path,
+    ) -> Result<RendererTab<'_>, Error> {
+        self.acquire_tab_slot();
+        let tab = self.open_tab().map_err(|message| {
+            self.release_tab_slot();
+            Error::BrowserLaunch { path: source_path.to_path_buf(), message }
+        })?;
+
+        Ok(RendererTab { tab, renderer: self })
+    }
+
+    /// Closes Chrome, if it was started.
+    pub fn shutdown(self) {
+        self.lock_browser().take();
+    }
+
+    // Opens a tab, starting or restarting Chrome if needed. Errors are
+    // described by the browser's message.
+    fn open_tab(&self) -> Result<Arc<Tab>, String> {
+        let mut browser = self.lock_browser();
+
+        // Replace a browser that has crashed or stopped responding
+        if let Some(running) = browser.as_ref() {
+            if running.get_version().is_err() {
+                println!(
+                    "{} headless Chrome stopped responding, restarting it",
+                    "warning".bright_yellow()
+                );
+                *browser = None;
+            }
+        }
+
+        if browser.is_none() {
+            *browser = Some(launch_browser()?);
+        }
+        let tab = match browser.as_ref().map(Browser::new_tab) {
+            Some(Ok(tab)) => tab,
+            // One restart, in case Chrome died since it was checked
+            _ => {
+                let restarted = launch_browser()?;
+                let tab =
+                    restarted.new_tab().map_err(|error| error.to_string())?;
+                *browser = Some(restarted);
+                tab
+            }
+        };
+
+        Ok(tab)
+    }
+
+    // Waits until fewer than the maximum number of tabs are open, then
+    // counts one more.
+    fn acquire_tab_slot(&self) {
+        let mut open_tabs = lock(&self.open_tabs);
+        while *open_tabs >= self.max_tabs {
+            open_tabs = self
+                .tab_closed
+                .wait(open_tabs)
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+        *open_tabs += 1;
+    }
+
+    // Counts one tab fewer and wakes a thread waiting for a tab.
+    fn release_tab_slot(&self) {
+        *lock(&self.open_tabs) -= 1;
+        self.tab_closed.notify_one();
+    }
+
+    fn lock_browser(&self) -> MutexGuard<'_, Option<Browser>> {
+        lock(&self.browser)
+    }
+}
+
+/// A tab handed out by a [`Renderer`], closed when it is dropped.
+pub struct RendererTab<'a> {
+    tab: Arc<Tab>,
+    renderer: &'a Renderer,
+}
+
+impl Deref for RendererTab<'_> {
+    type Target = Tab;
+
+    fn deref(&self) -> &Tab {
+        &self.tab
+    }
+}
+
+impl Drop for RendererTab<'_> {
+    fn drop(&mut self) {
+        // The browser may already be gone, in which case so is the tab
+        self.tab.close(false).ok();
+        self.renderer.release_tab_slot();
+    }
+}
+
+// Locks a mutex, even if another thread panicked while holding it.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+// Starts a new headless Chrome instance.
+fn launch_browser() -> Result<Browser, String> {
+    Browser::default().map_err(|error| error.to_string())
+}
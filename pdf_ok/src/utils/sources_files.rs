@@ -1,18 +1,158 @@
-/// Retrieves a list of source Front Matter YAML files.
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use walkdir::WalkDir;
+
+/// The directory searched when no source roots are supplied.
+pub const DEFAULT_SOURCE_DIRECTORY: &str = "./source_files";
+
+/// The pattern used to select Markdown files when no include patterns
+/// are supplied.
+pub const DEFAULT_INCLUDE_PATTERN: &str = "**/*.md";
+
+/// Retrieves the source Front Matter YAML files from the default
+/// `./source_files` directory.
+///
+/// # Errors
+///
+/// Returns a boxed error if the default directory cannot be walked.
 ///
 /// # Example
 ///
+/// ```no_run
+/// use pdf_ok::utils::get_source_front_matter_files;
+///
+/// let files = get_source_front_matter_files().unwrap();
+/// assert!(files.iter().all(|file| file.extension().unwrap() == "md"));
 /// ```
-/// use your_crate_name::get_source_front_matter_files;
+pub fn get_source_front_matter_files(
+) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    discover_source_files(
+        &[DEFAULT_SOURCE_DIRECTORY],
+        &[DEFAULT_INCLUDE_PATTERN],
+        &[],
+    )
+}
+
+/// Recursively discovers source files below one or more root
+/// directories.
+///
+/// Include and exclude patterns are glob patterns (e.g. `docs/**/*.md`)
+/// matched against each file's path relative to the root it was found
+/// in, using `/` as the separator. A `*` does not cross directory
+/// boundaries; use `**` for that. When no include patterns are given,
+/// [`DEFAULT_INCLUDE_PATTERN`] is used. A root that is a file rather than
+/// a directory is returned as-is unless it is excluded.
+///
+/// # Arguments
+///
+/// * `roots` - The directories (or files) to search.
+/// * `include_patterns` - Glob patterns a file must match to be
+///   returned.
+/// * `exclude_patterns` - Glob patterns that remove a file from the
+///   results, even if it matches an include pattern.
+///
+/// # Returns
+///
+/// The matching file paths. Files within a root are sorted by path and
+/// roots are visited in the order given, so the result is the same on
+/// every run. A file reachable from more than one root is only returned
+/// once.
 ///
-/// let files = get_source_front_matter_files();
-/// assert_eq!(files.len(), 2); // Ensure that two files are returned
-/// assert_eq!(files[0], "./source_files/404.md"); // Check the first file path
-/// assert_eq!(files[1], "./source_files/features.md"); // Check the second file path
+/// # Errors
+///
+/// Returns a boxed error if a pattern is not a valid glob, or if a root
+/// does not exist or cannot be read.
+///
+/// # Example
+///
+/// ```no_run
+/// use pdf_ok::utils::discover_source_files;
+///
+/// let files = discover_source_files(
+///     &["docs", "blog"],
+///     &["**/*.md"],
+///     &["**/drafts/**"],
+/// )
+/// .unwrap();
 /// ```
-pub fn get_source_front_matter_files() -> Vec<&'static str> {
-    vec![
-        "./source_files/404.md",
-        "./source_files/features.md",
-    ]
+pub fn discover_source_files<P: AsRef<Path>>(
+    roots: &[P],
+    include_patterns: &[&str],
+    exclude_patterns: &[&str],
+) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let include_set = if include_patterns.is_empty() {
+        build_glob_set(&[DEFAULT_INCLUDE_PATTERN])?
+    } else {
+        build_glob_set(include_patterns)?
+    };
+    let exclude_set = build_glob_set(exclude_patterns)?;
+
+    let mut seen: HashSet<PathBuf> = HashSet::new();
+    let mut source_files: Vec<PathBuf> = Vec::new();
+
+    for root in roots {
+        let root = root.as_ref();
+
+        // A file given directly as a root is taken at face value
+        if root.is_file() {
+            if !exclude_set.is_match(root) && seen.insert(dedup_key(root))
+            {
+                source_files.push(root.to_path_buf());
+            }
+            continue;
+        }
+
+        // Sorting the walk by file name keeps the output deterministic
+        for entry in WalkDir::new(root).sort_by_file_name() {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+
+            let path = entry.path();
+            let relative_path = relative_glob_path(root, path);
+            if include_set.is_match(&relative_path)
+                && !exclude_set.is_match(&relative_path)
+                && seen.insert(dedup_key(path))
+            {
+                source_files.push(path.to_path_buf());
+            }
+        }
+    }
+
+    Ok(source_files)
+}
+
+// Builds a single matcher from a list of glob patterns.
+fn build_glob_set(patterns: &[&str]) -> Result<GlobSet, globset::Error> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(GlobBuilder::new(pattern).literal_separator(true).build()?);
+    }
+    builder.build()
+}
+
+// The key a file is deduplicated by: its canonical path, or the path
+// without `.` components if it cannot be resolved, so that `./a.md` and
+// `a.md` count as the same file.
+fn dedup_key(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| {
+        path.components()
+            .filter(|component| !matches!(component, Component::CurDir))
+            .collect()
+    })
+}
+
+// Returns the path of `path` relative to `root`, with `/` separators, so
+// the same patterns work on every platform.
+fn relative_glob_path(root: &Path, path: &Path) -> String {
+    let relative_path = path.strip_prefix(root).unwrap_or(path);
+    relative_path
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
 }
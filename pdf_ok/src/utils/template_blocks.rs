@@ -0,0 +1,449 @@
+use regex::Regex;
+use std::collections::BTreeMap;
+
+use crate::utils::{
+    merge_markdown_yaml, resolve_path, FrontMatter, FrontMatterValue,
+    MergedMarkdown, TemplateError, TemplateOptions,
+};
+
+/// Renders the block tags and placeholders in Markdown content.
+///
+/// Block tags are written between `{%` and `%}` and are evaluated
+/// against the front matter before the placeholders are replaced:
+///
+/// * `{% if key %}…{% else %}…{% endif %}` keeps the first part if the
+///   value at `key` is set, and the optional `else` part otherwise. A
+///   value is unset if it is missing, null, `false`, `0`, or an empty
+///   string, list or mapping. `{% if not key %}` reverses the test.
+/// * `{% for item in key %}…{% endfor %}` repeats its content for each
+///   item of the list at `key`. Inside the loop, `{{item}}` (or
+///   `{{item.field}}`) is the current item, and `{{loop.index}}`,
+///   `{{loop.first}}` and `{{loop.last}}` describe its position.
+///
+/// `key` is a placeholder path, such as `author.name` or `tags[0]`. Blocks
+/// can be nested. A tag that is alone on its line is removed together
+/// with that line, so blocks do not leave blank lines in lists.
+///
+/// The remaining text is passed to
+/// [`merge_markdown_yaml`](crate::utils::merge_markdown_yaml).
+///
+/// # Arguments
+///
+/// * `front_matter` - The front matter values.
+/// * `markdown_content` - The Markdown content containing tags and
+///   placeholders.
+/// * `options` - How values are formatted and missing keys handled.
+///
+/// # Errors
+///
+/// Returns a [`TemplateError`] naming the line of an unknown or
+/// malformed tag, a block that is never closed, a `for` over a value that
+/// is not a list, or any placeholder error.
+///
+/// # Examples
+///
+/// ```
+/// use pdf_ok::utils::{
+///     parse_front_matter, render_markdown_template, FrontMatterFormat,
+///     TemplateOptions,
+/// };
+///
+/// let front_matter = parse_front_matter(
+///     "download: \"\"\ntags: [rust, pdf]",
+///     FrontMatterFormat::Yaml,
+/// )
+/// .unwrap();
+/// let markdown = "\
+/// {% if download %}
+/// [Download]({{download}})
+/// {% else %}
+/// No download.
+/// {% endif %}
+/// {% for tag in tags %}
+/// - {{loop.index}}. {{tag | upper}}
+/// {% endfor %}
+/// ";
+/// let rendered =
+///     render_markdown_template(&front_matter, markdown, &TemplateOptions::default())
+///         .unwrap();
+/// assert_eq!(rendered.content, "No download.\n- 1. RUST\n- 2. PDF\n");
+///
+/// let error = render_markdown_template(
+///     &front_matter,
+///     "Text\n{% for tag in tags %}\n{{tag}}\n",
+///     &TemplateOptions::default(),
+/// )
+/// .unwrap_err();
+/// assert_eq!(error.to_string(), "line 2: `{% for %}` is never closed");
+/// ```
+pub fn render_markdown_template(
+    front_matter: &FrontMatter,
+    markdown_content: &str,
+    options: &TemplateOptions,
+) -> Result<MergedMarkdown, TemplateError> {
+    let tokens = tokenize(markdown_content)?;
+    let mut parser = Parser { tokens: &tokens, position: 0 };
+    let nodes = parser.parse_nodes(&[])?;
+
+    let mut rendered =
+        MergedMarkdown { content: String::new(), warnings: Vec::new() };
+    render_nodes(&nodes, front_matter, options, &mut rendered)?;
+
+    Ok(rendered)
+}
+
+// A piece of the template, before it is parsed into blocks.
+#[derive(Debug)]
+enum Token<'a> {
+    Text { text: &'a str, line: usize },
+    Tag { tag: &'a str, line: usize },
+}
+
+// A parsed template.
+#[derive(Debug)]
+enum Node<'a> {
+    Text {
+        text: &'a str,
+        line: usize,
+    },
+    If {
+        path: &'a str,
+        negated: bool,
+        then_nodes: Vec<Node<'a>>,
+        else_nodes: Vec<Node<'a>>,
+    },
+    For {
+        variable: &'a str,
+        path: &'a str,
+        body: Vec<Node<'a>>,
+        line: usize,
+    },
+}
+
+// Splits the content into text and `{% … %}` tags. A tag alone on its
+// line swallows the line's indentation and line ending.
+fn tokenize(content: &str) -> Result<Vec<Token<'_>>, TemplateError> {
+    let tag_regex = Regex::new(r"\{%(.*?)%\}").unwrap();
+    let mut tokens = Vec::new();
+    let mut text_start = 0;
+
+    for captures in tag_regex.captures_iter(content) {
+        let tag_match = captures.get(0).unwrap();
+        let line = line_number(content, tag_match.start());
+
+        let line_start = content[..tag_match.start()]
+            .rfind('\n')
+            .map_or(0, |i| i + 1);
+        let rest_of_line_end = content[tag_match.end()..]
+            .find('\n')
+            .map(|i| tag_match.end() + i);
+        let is_alone_on_line =
+            content[line_start..tag_match.start()].trim().is_empty()
+                && content[tag_match.end()
+                    ..rest_of_line_end.unwrap_or(content.len())]
+                    .trim()
+                    .is_empty();
+
+        let (token_start, token_end) = if is_alone_on_line {
+            let end = rest_of_line_end.map_or(content.len(), |i| i + 1);
+            (line_start.max(text_start), end)
+        } else {
+            (tag_match.start(), tag_match.end())
+        };
+
+        if token_start > text_start {
+            tokens.push(Token::Text {
+                text: &content[text_start..token_start],
+                line: line_number(content, text_start),
+            });
+        }
+        tokens.push(Token::Tag {
+            tag: captures.get(1).unwrap().as_str().trim(),
+            line,
+        });
+        text_start = token_end;
+    }
+
+    if text_start < content.len() {
+        tokens.push(Token::Text {
+            text: &content[text_start..],
+            line: line_number(content, text_start),
+        });
+    }
+
+    // A `{%` left in the text has no matching `%}` on its line
+    for token in &tokens {
+        if let Token::Text { text, line } = token {
+            if let Some(offset) = text.find("{%") {
+                return Err(TemplateError {
+                    line: line + line_number(text, offset) - 1,
+                    message: "`{%` is never closed by `%}`".to_string(),
+                });
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+// Builds the block structure from the tokens.
+struct Parser<'t, 'a> {
+    tokens: &'t [Token<'a>],
+    position: usize,
+}
+
+impl<'a> Parser<'_, 'a> {
+    // Parses nodes until the end of the tokens, or until one of the
+    // `closing` tags is reached (which is left unconsumed).
+    fn parse_nodes(
+        &mut self,
+        closing: &[&str],
+    ) -> Result<Vec<Node<'a>>, TemplateError> {
+        let mut nodes = Vec::new();
+
+        while let Some(token) = self.tokens.get(self.position) {
+            match *token {
+                Token::Text { text, line } => {
+                    nodes.push(Node::Text { text, line });
+                    self.position += 1;
+                }
+                Token::Tag { tag, line } => {
+                    let keyword = tag
+                        .split_whitespace()
+                        .next()
+                        .unwrap_or_default();
+                    if closing.contains(&keyword) {
+                        return Ok(nodes);
+                    }
+                    self.position += 1;
+                    nodes.push(match keyword {
+                        "if" => self.parse_if(tag, line)?,
+                        "for" => self.parse_for(tag, line)?,
+                        _ => return Err(unexpected_tag(token)),
+                    });
+                }
+            }
+        }
+
+        Ok(nodes)
+    }
+
+    // Parses `if [not] path`, its content and the optional `else` part.
+    fn parse_if(
+        &mut self,
+        tag: &'a str,
+        line: usize,
+    ) -> Result<Node<'a>, TemplateError> {
+        let words: Vec<&str> = tag.split_whitespace().collect();
+        let (path, negated) = match words.as_slice() {
+            ["if", "not", _] => (tag_argument(tag, "not"), true),
+            ["if", _] => (tag_argument(tag, "if"), false),
+            _ => {
+                return Err(TemplateError {
+                    line,
+                    message: format!("malformed tag `{{% {} %}}`, expected `{{% if key %}}`", tag),
+                })
+            }
+        };
+
+        let then_nodes = self.parse_nodes(&["else", "endif"])?;
+        let else_nodes = if self.next_keyword() == Some("else") {
+            self.expect_bare_tag("else")?;
+            self.parse_nodes(&["endif"])?
+        } else {
+            Vec::new()
+        };
+        if self.next_keyword() != Some("endif") {
+            return Err(never_closed("if", line));
+        }
+        self.expect_bare_tag("endif")?;
+
+        Ok(Node::If { path, negated, then_nodes, else_nodes })
+    }
+
+    // Parses `for variable in path` and its content.
+    fn parse_for(
+        &mut self,
+        tag: &'a str,
+        line: usize,
+    ) -> Result<Node<'a>, TemplateError> {
+        let words: Vec<&'a str> = tag.split_whitespace().collect();
+        let ["for", variable, "in", _] = words.as_slice() else {
+            return Err(TemplateError {
+                line,
+                message: format!(
+                    "malformed tag `{{% {} %}}`, expected `{{% for item in key %}}`",
+                    tag
+                ),
+            });
+        };
+        let path = tag_argument(tag, "in");
+
+        let body = self.parse_nodes(&["endfor"])?;
+        if self.next_keyword() != Some("endfor") {
+            return Err(never_closed("for", line));
+        }
+        self.expect_bare_tag("endfor")?;
+
+        Ok(Node::For { variable, path, body, line })
+    }
+
+    // Returns the keyword of the next token if it is a tag.
+    fn next_keyword(&self) -> Option<&'a str> {
+        match self.tokens.get(self.position)? {
+            Token::Tag { tag, .. } => tag.split_whitespace().next(),
+            Token::Text { .. } => None,
+        }
+    }
+
+    // Consumes a tag that must consist of the keyword alone.
+    fn expect_bare_tag(
+        &mut self,
+        keyword: &str,
+    ) -> Result<(), TemplateError> {
+        let token = &self.tokens[self.position];
+        self.position += 1;
+        match token {
+            Token::Tag { tag, .. } if *tag == keyword => Ok(()),
+            _ => Err(unexpected_tag(token)),
+        }
+    }
+}
+
+// Renders parsed nodes into `rendered`, with `scope` holding the front
+// matter and any loop variables.
+fn render_nodes(
+    nodes: &[Node<'_>],
+    scope: &FrontMatter,
+    options: &TemplateOptions,
+    rendered: &mut MergedMarkdown,
+) -> Result<(), TemplateError> {
+    for node in nodes {
+        match node {
+            Node::Text { text, line } => {
+                let to_content_line =
+                    |text_line: usize| line + text_line - 1;
+                let merged = merge_markdown_yaml(scope, text, options)
+                    .map_err(|error| TemplateError {
+                        line: to_content_line(error.line),
+                        ..error
+                    })?;
+                rendered.content.push_str(&merged.content);
+                rendered.warnings.extend(
+                    merged.warnings.into_iter().map(|warning| {
+                        TemplateError {
+                            line: to_content_line(warning.line),
+                            ..warning
+                        }
+                    }),
+                );
+            }
+            Node::If { path, negated, then_nodes, else_nodes } => {
+                let is_set =
+                    resolve_path(scope, path).is_some_and(is_truthy);
+                let branch = if is_set != *negated {
+                    then_nodes
+                } else {
+                    else_nodes
+                };
+                render_nodes(branch, scope, options, rendered)?;
+            }
+            Node::For { variable, path, body, line } => {
+                let items = match resolve_path(scope, path) {
+                    None | Some(FrontMatterValue::Null) => Vec::new(),
+                    Some(FrontMatterValue::Sequence(items)) => {
+                        items.clone()
+                    }
+                    Some(_) => {
+                        return Err(TemplateError {
+                            line: *line,
+                            message: format!(
+                                "`{}` is not a list",
+                                path
+                            ),
+                        })
+                    }
+                };
+
+                let count = items.len();
+                for (index, item) in items.into_iter().enumerate() {
+                    let mut loop_scope = scope.clone();
+                    loop_scope.insert(variable.to_string(), item);
+                    loop_scope.insert(
+                        "loop".to_string(),
+                        FrontMatterValue::Mapping(BTreeMap::from([
+                            (
+                                "index".to_string(),
+                                FrontMatterValue::Integer(
+                                    index as i64 + 1,
+                                ),
+                            ),
+                            (
+                                "first".to_string(),
+                                FrontMatterValue::Bool(index == 0),
+                            ),
+                            (
+                                "last".to_string(),
+                                FrontMatterValue::Bool(
+                                    index + 1 == count,
+                                ),
+                            ),
+                        ])),
+                    );
+                    render_nodes(body, &loop_scope, options, rendered)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Decides whether an `if` block is shown for a value.
+fn is_truthy(value: &FrontMatterValue) -> bool {
+    match value {
+        FrontMatterValue::Null => false,
+        FrontMatterValue::Bool(bool_value) => *bool_value,
+        FrontMatterValue::Integer(integer) => *integer != 0,
+        FrontMatterValue::Float(float) => *float != 0.0,
+        FrontMatterValue::String(string_value) => {
+            !string_value.is_empty()
+        }
+        FrontMatterValue::Sequence(sequence) => !sequence.is_empty(),
+        FrontMatterValue::Mapping(mapping) => !mapping.is_empty(),
+    }
+}
+
+// Returns the text of a tag after the given word, e.g. the path after
+// `in` in `for tag in tags`.
+fn tag_argument<'a>(tag: &'a str, word: &str) -> &'a str {
+    tag.split_whitespace()
+        .skip_while(|part| *part != word)
+        .nth(1)
+        .unwrap_or_default()
+}
+
+fn unexpected_tag(token: &Token<'_>) -> TemplateError {
+    match token {
+        Token::Tag { tag, line } => TemplateError {
+            line: *line,
+            message: format!("unexpected tag `{{% {} %}}`", tag),
+        },
+        Token::Text { line, .. } => TemplateError {
+            line: *line,
+            message: "unexpected text".to_string(),
+        },
+    }
+}
+
+fn never_closed(keyword: &str, line: usize) -> TemplateError {
+    TemplateError {
+        line,
+        message: format!("`{{% {} %}}` is never closed", keyword),
+    }
+}
+
+// Returns the 1-based line number of a byte offset in the content.
+fn line_number(content: &str, offset: usize) -> usize {
+    content[..offset].matches('\n').count() + 1
+}
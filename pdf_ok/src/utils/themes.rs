@@ -0,0 +1,318 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use regex::{Captures, Regex};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::utils::{FrontMatter, FrontMatterValue};
+use crate::Error;
+
+/// The directory themes are read from when none is configured.
+pub const DEFAULT_THEMES_DIRECTORY: &str = "./themes";
+
+/// The theme used when neither the build nor the front matter names one.
+pub const DEFAULT_THEME: &str = "print";
+
+/// The theme name that turns styling off, leaving Chrome's defaults.
+pub const NO_THEME: &str = "none";
+
+// The themes compiled into the binary, by name
+const BUILT_IN_THEMES: [(&str, &str); 1] =
+    [("print", include_str!("../../themes/print.css"))];
+
+/// A named bundle of CSS that is inlined into a document before it is
+/// printed.
+///
+/// Local files referenced with `url(...)`, such as web fonts in an
+/// `@font-face` rule, have already been replaced with `data:` URIs, so
+/// the stylesheet does not depend on the location of the theme.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    /// The name of the theme, e.g. `corporate`.
+    pub name: String,
+    /// The CSS of the theme.
+    pub stylesheet: String,
+}
+
+/// Loads a theme by name.
+///
+/// A theme `name` is either the single file `<themes_directory>/<name>.css`
+/// or the directory `<themes_directory>/<name>/`, whose `.css` files are
+/// joined in file name order. If neither exists, a built-in theme of
+/// that name is used. The name `none` gives an empty theme. Names
+/// containing `/`, `\` or `..` could point outside the themes directory,
+/// so they are rejected.
+///
+/// Relative `url(...)` references are resolved against the file they are
+/// in and embedded as base64 `data:` URIs. They must stay within the
+/// directory of that file.
+///
+/// # Arguments
+///
+/// * `name` - The name of the theme.
+/// * `themes_directory` - The directory containing the themes.
+/// * `source_path` - The source file the theme is for, which errors are
+///   reported against.
+///
+/// # Errors
+///
+/// Returns an [`Error::Theme`] if the name is not valid, if there is no
+/// theme of that name, or if a stylesheet references a file outside its
+/// directory, and an [`Error::Io`] if a stylesheet or a file it
+/// references cannot be read.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+///
+/// use pdf_ok::utils::load_theme;
+///
+/// let source = Path::new("hello.md");
+/// let theme = load_theme("print", "no_such_directory", source).unwrap();
+/// assert!(theme.stylesheet.contains("@page"));
+/// assert!(load_theme("no_such_theme", "no_such_directory", source).is_err());
+///
+/// let error = load_theme("../secret", "themes", source).unwrap_err();
+/// assert!(error.to_string().starts_with("hello.md: "));
+/// ```
+pub fn load_theme<P: AsRef<Path>>(
+    name: &str,
+    themes_directory: P,
+    source_path: &Path,
+) -> Result<Theme, Error> {
+    let themes_directory = themes_directory.as_ref();
+    if name == NO_THEME {
+        return Ok(Theme { name: name.to_string(), stylesheet: String::new() });
+    }
+    if !is_theme_name(name) {
+        return Err(Error::Theme {
+            path: source_path.to_path_buf(),
+            message: format!("theme \"{}\" is not a file name", name),
+        });
+    }
+
+    let file_path = themes_directory.join(format!("{}.css", name));
+    let bundle_path = themes_directory.join(name);
+    let stylesheet_paths = if file_path.is_file() {
+        vec![file_path]
+    } else if bundle_path.is_dir() {
+        stylesheets_in(&bundle_path)?
+    } else if let Some((_, stylesheet)) =
+        BUILT_IN_THEMES.iter().find(|(built_in, _)| *built_in == name)
+    {
+        return Ok(Theme {
+            name: name.to_string(),
+            stylesheet: stylesheet.to_string(),
+        });
+    } else {
+        return Err(Error::Theme {
+            path: source_path.to_path_buf(),
+            message: format!(
+                "no theme named \"{}\" in {}",
+                name,
+                themes_directory.display()
+            ),
+        });
+    };
+
+    let mut stylesheet = String::new();
+    for path in stylesheet_paths {
+        let css = fs::read_to_string(&path)
+            .map_err(|source| Error::Io { path: path.clone(), source })?;
+        let base_directory = path.parent().unwrap_or(Path::new("."));
+        stylesheet.push_str(&embed_local_urls(
+            &css,
+            base_directory,
+            source_path,
+        )?);
+        stylesheet.push('\n');
+    }
+
+    Ok(Theme { name: name.to_string(), stylesheet })
+}
+
+/// Loads the theme for a document: the one named by its `theme` front
+/// matter key, or `default_theme` if there is none.
+///
+/// # Errors
+///
+/// See [`load_theme`]. Errors are reported against `source_path`.
+pub fn select_theme<P: AsRef<Path>>(
+    front_matter: &FrontMatter,
+    default_theme: &str,
+    themes_directory: P,
+    source_path: &Path,
+) -> Result<Theme, Error> {
+    let name = front_matter
+        .get("theme")
+        .and_then(FrontMatterValue::as_str)
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .unwrap_or(default_theme);
+
+    load_theme(name, themes_directory, source_path)
+}
+
+/// Inlines the stylesheet of a theme into an HTML document.
+///
+/// The stylesheet is added as a `<style>` element at the end of the
+/// `<head>`, so it comes after, and overrides, any styles of the layout
+/// with the same specificity. A document without a `</head>` gets the
+/// element at the start.
+///
+/// # Examples
+///
+/// ```
+/// use pdf_ok::utils::{inline_theme, Theme};
+///
+/// let theme = Theme {
+///     name: "plain".to_string(),
+///     stylesheet: "h1 { color: red; }".to_string(),
+/// };
+/// assert_eq!(
+///     inline_theme("<head><title>x</title></head><h1>x</h1>", &theme),
+///     "<head><title>x</title><style>\nh1 { color: red; }\n</style>\n</head><h1>x</h1>"
+/// );
+/// ```
+pub fn inline_theme(html: &str, theme: &Theme) -> String {
+    if theme.stylesheet.trim().is_empty() {
+        return html.to_string();
+    }
+
+    let style = format!("<style>\n{}\n</style>\n", theme.stylesheet.trim_end());
+    // Lowercasing ASCII keeps the byte offsets of the original
+    let position =
+        html.to_ascii_lowercase().find("</head>").unwrap_or_default();
+
+    let mut inlined = String::with_capacity(html.len() + style.len());
+    inlined.push_str(&html[..position]);
+    inlined.push_str(&style);
+    inlined.push_str(&html[position..]);
+    inlined
+}
+
+// Lists the stylesheets of a theme directory in file name order.
+fn stylesheets_in(directory: &Path) -> Result<Vec<PathBuf>, Error> {
+    let entries = fs::read_dir(directory)
+        .map_err(|source| Error::Io { path: directory.to_path_buf(), source })?;
+
+    let mut paths = Vec::new();
+    for entry in entries {
+        let path = entry
+            .map_err(|source| Error::Io {
+                path: directory.to_path_buf(),
+                source,
+            })?
+            .path();
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "css") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    Ok(paths)
+}
+
+// Replaces every `url(...)` that refers to a local file with a base64
+// `data:` URI of its content. Remote URLs, data URIs and fragment
+// references are left as they are.
+fn embed_local_urls(
+    css: &str,
+    base_directory: &Path,
+    source_path: &Path,
+) -> Result<String, Error> {
+    let url_regex =
+        Regex::new(r#"url\(\s*(?:"([^"]*)"|'([^']*)'|([^'")\s]+))\s*\)"#)
+            .unwrap();
+    let mut first_error: Option<Error> = None;
+
+    let embedded = url_regex.replace_all(css, |captures: &Captures<'_>| {
+        let url = captures
+            .get(1)
+            .or_else(|| captures.get(2))
+            .or_else(|| captures.get(3))
+            .map_or("", |url| url.as_str());
+        if url.is_empty()
+            || url.starts_with('#')
+            || url.starts_with("data:")
+            || url.contains("://")
+        {
+            return captures[0].to_string();
+        }
+
+        // Drop any query or fragment, e.g. `font.svg#family`
+        let file = url.split(['?', '#']).next().unwrap_or(url);
+        let path = base_directory.join(file);
+        match read_referenced_file(&path, base_directory, source_path) {
+            Ok(bytes) => format!(
+                "url(\"data:{};base64,{}\")",
+                media_type(&path),
+                STANDARD.encode(bytes)
+            ),
+            Err(error) => {
+                first_error.get_or_insert(error);
+                captures[0].to_string()
+            }
+        }
+    });
+
+    match first_error {
+        Some(error) => Err(error),
+        None => Ok(embedded.to_string()),
+    }
+}
+
+// Reads a file referenced from a stylesheet, refusing one that resolves
+// to somewhere outside the directory of the stylesheet.
+fn read_referenced_file(
+    path: &Path,
+    base_directory: &Path,
+    source_path: &Path,
+) -> Result<Vec<u8>, Error> {
+    let canonical = |path: &Path| {
+        fs::canonicalize(path)
+            .map_err(|source| Error::Io { path: path.to_path_buf(), source })
+    };
+    let resolved = canonical(path)?;
+    if !resolved.starts_with(canonical(base_directory)?) {
+        return Err(Error::Theme {
+            path: source_path.to_path_buf(),
+            message: format!(
+                "{} is outside the theme directory {}",
+                path.display(),
+                base_directory.display()
+            ),
+        });
+    }
+
+    fs::read(&resolved)
+        .map_err(|source| Error::Io { path: path.to_path_buf(), source })
+}
+
+// The media type of a file referenced from a stylesheet, by extension.
+fn media_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "woff2" => "font/woff2",
+        "woff" => "font/woff",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        _ => "application/octet-stream",
+    }
+}
+
+// Whether a theme name names a file or directory directly inside the
+// themes directory.
+fn is_theme_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains(['/', '\\']) && !name.contains("..")
+}
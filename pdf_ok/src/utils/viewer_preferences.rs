@@ -0,0 +1,303 @@
+use lopdf::{dictionary, Document, Object as LopdfObject};
+use std::str::FromStr;
+
+use crate::utils::{FrontMatter, FrontMatterValue};
+
+/// How a viewer arranges the pages of a document, written as the
+/// Catalog's `/PageLayout`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageLayout {
+    /// One page at a time.
+    SinglePage,
+    /// The pages in one continuous column.
+    OneColumn,
+    /// The pages in two columns, odd pages on the left.
+    TwoColumnLeft,
+    /// The pages in two columns, odd pages on the right.
+    TwoColumnRight,
+    /// Two pages at a time, odd pages on the left.
+    TwoPageLeft,
+    /// Two pages at a time, odd pages on the right.
+    TwoPageRight,
+}
+
+impl PageLayout {
+    // The PDF name of the layout.
+    fn name(self) -> &'static str {
+        match self {
+            PageLayout::SinglePage => "SinglePage",
+            PageLayout::OneColumn => "OneColumn",
+            PageLayout::TwoColumnLeft => "TwoColumnLeft",
+            PageLayout::TwoColumnRight => "TwoColumnRight",
+            PageLayout::TwoPageLeft => "TwoPageLeft",
+            PageLayout::TwoPageRight => "TwoPageRight",
+        }
+    }
+}
+
+impl FromStr for PageLayout {
+    type Err = String;
+
+    /// Reads a layout name, ignoring case, `_` and `-`, e.g.
+    /// `single_page` or `TwoColumnLeft`.
+    fn from_str(page_layout: &str) -> Result<Self, Self::Err> {
+        match simplify(page_layout).as_str() {
+            "singlepage" => Ok(PageLayout::SinglePage),
+            "onecolumn" => Ok(PageLayout::OneColumn),
+            "twocolumnleft" => Ok(PageLayout::TwoColumnLeft),
+            "twocolumnright" => Ok(PageLayout::TwoColumnRight),
+            "twopageleft" => Ok(PageLayout::TwoPageLeft),
+            "twopageright" => Ok(PageLayout::TwoPageRight),
+            _ => Err(format!(
+                "unknown page layout \"{}\" (expected single_page, one_column, two_column_left, two_column_right, two_page_left or two_page_right)",
+                page_layout.trim()
+            )),
+        }
+    }
+}
+
+/// Which panel a viewer opens the document with, written as the
+/// Catalog's `/PageMode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageMode {
+    /// No panel.
+    UseNone,
+    /// The outline (bookmarks) panel.
+    UseOutlines,
+    /// The page thumbnails panel.
+    UseThumbs,
+    /// Full screen, without menus or panels.
+    FullScreen,
+    /// The optional content (layers) panel.
+    UseOC,
+    /// The attachments panel.
+    UseAttachments,
+}
+
+impl PageMode {
+    // The PDF name of the mode.
+    fn name(self) -> &'static str {
+        match self {
+            PageMode::UseNone => "UseNone",
+            PageMode::UseOutlines => "UseOutlines",
+            PageMode::UseThumbs => "UseThumbs",
+            PageMode::FullScreen => "FullScreen",
+            PageMode::UseOC => "UseOC",
+            PageMode::UseAttachments => "UseAttachments",
+        }
+    }
+}
+
+impl FromStr for PageMode {
+    type Err = String;
+
+    /// Reads a mode name, ignoring case, `_`, `-` and a leading `use`,
+    /// e.g. `outlines`, `full_screen` or `UseThumbs`.
+    fn from_str(page_mode: &str) -> Result<Self, Self::Err> {
+        let simplified = simplify(page_mode);
+        match simplified.strip_prefix("use").unwrap_or(&simplified) {
+            "none" => Ok(PageMode::UseNone),
+            "outlines" => Ok(PageMode::UseOutlines),
+            "thumbs" => Ok(PageMode::UseThumbs),
+            "fullscreen" => Ok(PageMode::FullScreen),
+            "oc" => Ok(PageMode::UseOC),
+            "attachments" => Ok(PageMode::UseAttachments),
+            _ => Err(format!(
+                "unknown page mode \"{}\" (expected none, outlines, thumbs, full_screen, oc or attachments)",
+                page_mode.trim()
+            )),
+        }
+    }
+}
+
+/// The magnification a viewer opens the first page with, written as the
+/// Catalog's `/OpenAction`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Zoom {
+    /// The whole page fits the window.
+    FitPage,
+    /// The width of the page fits the window.
+    FitWidth,
+    /// A fixed magnification, where `1.0` is 100%.
+    Factor(f64),
+}
+
+impl FromStr for Zoom {
+    type Err = String;
+
+    /// Reads `fit`, `fit_width` or a percentage such as `150%` or `150`.
+    fn from_str(zoom: &str) -> Result<Self, Self::Err> {
+        let simplified = simplify(zoom);
+        match simplified.as_str() {
+            "fit" | "fitpage" => return Ok(Zoom::FitPage),
+            "fitwidth" => return Ok(Zoom::FitWidth),
+            _ => {}
+        }
+
+        simplified
+            .trim_end_matches('%')
+            .parse::<f64>()
+            .ok()
+            .filter(|percent| percent.is_finite() && *percent > 0.0)
+            .map(|percent| Zoom::Factor(percent / 100.0))
+            .ok_or_else(|| {
+                format!(
+                    "unknown zoom \"{}\" (expected fit, fit_width or a percentage)",
+                    zoom.trim()
+                )
+            })
+    }
+}
+
+/// How a viewer presents a document when it is opened.
+///
+/// # Examples
+///
+/// ```
+/// use pdf_ok::utils::{
+///     parse_front_matter, FrontMatterFormat, PageLayout, PageMode,
+///     ViewerPreferences, Zoom,
+/// };
+///
+/// let front_matter = parse_front_matter(
+///     "page_layout: two_page_right\npage_mode: outlines\nzoom: 125%",
+///     FrontMatterFormat::Yaml,
+/// )
+/// .unwrap();
+/// let preferences =
+///     ViewerPreferences::default().with_front_matter(&front_matter).unwrap();
+/// assert!(preferences.display_doc_title);
+/// assert_eq!(preferences.page_layout, Some(PageLayout::TwoPageRight));
+/// assert_eq!(preferences.page_mode, Some(PageMode::UseOutlines));
+/// assert_eq!(preferences.zoom, Some(Zoom::Factor(1.25)));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ViewerPreferences {
+    /// Whether the window bar shows the document title rather than the
+    /// file name.
+    pub display_doc_title: bool,
+    /// How the pages are arranged, or `None` for the viewer's default.
+    pub page_layout: Option<PageLayout>,
+    /// Which panel is open, or `None` for the viewer's default.
+    pub page_mode: Option<PageMode>,
+    /// The initial magnification, or `None` for the viewer's default.
+    pub zoom: Option<Zoom>,
+}
+
+impl Default for ViewerPreferences {
+    fn default() -> Self {
+        ViewerPreferences {
+            display_doc_title: true,
+            page_layout: None,
+            page_mode: None,
+            zoom: None,
+        }
+    }
+}
+
+impl ViewerPreferences {
+    /// Returns a copy of the preferences with the `display_doc_title`,
+    /// `page_layout`, `page_mode` and `zoom` front matter values of a
+    /// document applied.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the problem if one of the values is not
+    /// valid.
+    pub fn with_front_matter(
+        &self,
+        front_matter: &FrontMatter,
+    ) -> Result<ViewerPreferences, String> {
+        let mut preferences = *self;
+        let value = |key: &str| {
+            front_matter.get(key).and_then(FrontMatterValue::as_str)
+        };
+
+        match front_matter.get("display_doc_title") {
+            Some(FrontMatterValue::Bool(display)) => {
+                preferences.display_doc_title = *display;
+            }
+            Some(FrontMatterValue::Null) | None => {}
+            Some(_) => {
+                return Err("invalid display_doc_title (expected true or false)"
+                    .to_string())
+            }
+        }
+        if let Some(page_layout) = value("page_layout") {
+            preferences.page_layout = Some(page_layout.parse()?);
+        }
+        if let Some(page_mode) = value("page_mode") {
+            preferences.page_mode = Some(page_mode.parse()?);
+        }
+        // A number alone is a percentage
+        match front_matter.get("zoom") {
+            Some(FrontMatterValue::String(zoom)) => {
+                preferences.zoom = Some(zoom.parse()?);
+            }
+            Some(FrontMatterValue::Integer(percent)) => {
+                preferences.zoom = Some(percent.to_string().parse()?);
+            }
+            Some(FrontMatterValue::Float(percent)) => {
+                preferences.zoom = Some(percent.to_string().parse()?);
+            }
+            Some(FrontMatterValue::Null) | None => {}
+            Some(_) => {
+                return Err(
+                    "invalid zoom (expected fit, fit_width or a percentage)"
+                        .to_string(),
+                )
+            }
+        }
+
+        Ok(preferences)
+    }
+
+    /// Writes the preferences into the Catalog of a document: the
+    /// `/ViewerPreferences` dictionary, `/PageLayout`, `/PageMode`, and
+    /// an `/OpenAction` that shows the first page at the zoom.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the document has no Catalog.
+    pub fn apply(&self, doc: &mut Document) -> Result<(), lopdf::Error> {
+        let first_page = doc.get_pages().values().next().copied();
+        let catalog = doc.catalog_mut()?;
+
+        catalog.set(
+            "ViewerPreferences",
+            dictionary! { "DisplayDocTitle" => self.display_doc_title },
+        );
+        if let Some(page_layout) = self.page_layout {
+            catalog.set("PageLayout", LopdfObject::Name(page_layout.name().into()));
+        }
+        if let Some(page_mode) = self.page_mode {
+            catalog.set("PageMode", LopdfObject::Name(page_mode.name().into()));
+        }
+        if let (Some(zoom), Some(first_page)) = (self.zoom, first_page) {
+            let destination = match zoom {
+                Zoom::FitPage => vec![first_page.into(), "Fit".into()],
+                Zoom::FitWidth => {
+                    vec![first_page.into(), "FitH".into(), LopdfObject::Null]
+                }
+                Zoom::Factor(factor) => vec![
+                    first_page.into(),
+                    "XYZ".into(),
+                    LopdfObject::Null,
+                    LopdfObject::Null,
+                    LopdfObject::Real(factor as f32),
+                ],
+            };
+            catalog.set("OpenAction", destination);
+        }
+
+        Ok(())
+    }
+}
+
+// Lower cases a name and drops the spaces, `_` and `-` in it.
+fn simplify(name: &str) -> String {
+    name.chars()
+        .filter(|character| !matches!(character, ' ' | '_' | '-'))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
@@ -0,0 +1,144 @@
+use colored::Colorize;
+use notify_debouncer_mini::notify::RecursiveMode;
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult};
+use std::collections::HashSet;
+use std::error::Error as StdError;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use crate::utils::{discover_source_files, read_file_data, BuildConfig};
+use crate::Error;
+
+/// How long the file system has to be quiet before a burst of changes
+/// triggers a rebuild.
+pub const WATCH_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Builds the source files, then watches them and rebuilds the PDFs
+/// affected by each change until the process is stopped.
+///
+/// The source directories, the layouts directory and the themes
+/// directory are watched. A changed source file is rebuilt on its own;
+/// a changed layout or stylesheet rebuilds every source file, of which
+/// the [build cache](crate::utils::BuildCache) skips those that do not
+/// use it. Bursts of changes are combined into one rebuild, and a file
+/// that fails is reported without stopping the watcher.
+///
+/// # Arguments
+///
+/// * `roots` - The files and directories to read.
+/// * `include_patterns` - Glob patterns of files to include, as for
+///   [`discover_source_files`].
+/// * `exclude_patterns` - Glob patterns of files to leave out.
+/// * `config` - The settings shared by every document in the build.
+///
+/// # Errors
+///
+/// Returns an error if the source files cannot be discovered or the
+/// directories cannot be watched. Errors in individual documents are
+/// only reported.
+///
+/// # Examples
+///
+/// ```no_run
+/// use pdf_ok::utils::{watch_source_files, BuildConfig};
+///
+/// watch_source_files(&["docs"], &["**/*.md"], &[], &BuildConfig::default())
+///     .unwrap();
+/// ```
+pub fn watch_source_files<P: AsRef<Path>>(
+    roots: &[P],
+    include_patterns: &[&str],
+    exclude_patterns: &[&str],
+    config: &BuildConfig,
+) -> Result<(), Box<dyn StdError>> {
+    let discover =
+        || discover_source_files(roots, include_patterns, exclude_patterns);
+
+    rebuild(discover()?, config);
+
+    let (sender, receiver) = mpsc::channel::<DebounceEventResult>();
+    let mut debouncer = new_debouncer(WATCH_DEBOUNCE, sender)?;
+    let style_directories =
+        [config.layouts_directory.as_path(), config.themes_directory.as_path()];
+    let watched = roots
+        .iter()
+        .map(AsRef::as_ref)
+        .chain(style_directories)
+        .filter(|path| path.exists());
+    for path in watched {
+        debouncer.watcher().watch(path, RecursiveMode::Recursive)?;
+        println!("{} {}", "Watching".cyan(), path.display());
+    }
+    let style_directories: Vec<PathBuf> = style_directories
+        .iter()
+        .filter_map(|directory| fs::canonicalize(directory).ok())
+        .collect();
+
+    for events in receiver {
+        let changed: HashSet<PathBuf> = match events {
+            Ok(events) => events
+                .into_iter()
+                .map(|event| canonical(&event.path))
+                .collect(),
+            Err(error) => {
+                println!("{} {}", "watch error".bright_red(), error);
+                continue;
+            }
+        };
+
+        let files = match discover() {
+            Ok(files) => files,
+            Err(error) => {
+                println!("{} {}", "error".bright_red(), error);
+                continue;
+            }
+        };
+        let style_changed = changed.iter().any(|path| {
+            style_directories
+                .iter()
+                .any(|directory| path.starts_with(directory))
+        });
+        let affected: Vec<PathBuf> = if style_changed {
+            files
+        } else {
+            files
+                .into_iter()
+                .filter(|file| changed.contains(&canonical(file)))
+                .collect()
+        };
+
+        if !affected.is_empty() {
+            rebuild(affected, config);
+        }
+    }
+
+    Ok(())
+}
+
+// Builds the given files and prints a one-line summary, with the errors
+// of any files that failed.
+fn rebuild(files: Vec<PathBuf>, config: &BuildConfig) {
+    let started = Instant::now();
+    let results = read_file_data(files, config);
+
+    let failed: Vec<&Error> =
+        results.iter().filter_map(|result| result.as_ref().err()).collect();
+    for error in &failed {
+        println!("{} {}", "error".bright_red(), error);
+    }
+    println!(
+        "{} {} of {} documents built in {:.1}s",
+        "Rebuilt:".cyan(),
+        results.len() - failed.len(),
+        results.len(),
+        started.elapsed().as_secs_f64()
+    );
+}
+
+// Resolves a path for comparison, keeping it as it is if it no longer
+// exists.
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
@@ -0,0 +1,379 @@
+use chrono::SecondsFormat;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use crate::utils::{
+    format_value, parse_pdf_date, resolve_path, FrontMatter, TemplateOptions,
+};
+
+// The namespaces every XMP packet declares, by prefix
+const BUILT_IN_NAMESPACES: [(&str, &str); 3] = [
+    ("dc", "http://purl.org/dc/elements/1.1/"),
+    ("xmp", "http://ns.adobe.com/xap/1.0/"),
+    ("pdf", "http://ns.adobe.com/pdf/1.3/"),
+];
+
+// The properties every XMP packet derives from the Info dictionary
+const DERIVED_PROPERTIES: [&str; 10] = [
+    "dc:title",
+    "dc:creator",
+    "dc:description",
+    "dc:subject",
+    "dc:language",
+    "xmp:CreateDate",
+    "xmp:ModifyDate",
+    "xmp:CreatorTool",
+    "pdf:Producer",
+    "pdf:Keywords",
+];
+
+// Prefixes that cannot be declared, as the packet itself uses them
+const RESERVED_PREFIXES: [&str; 4] = ["x", "rdf", "xml", "xmlns"];
+
+/// An XMP namespace declared for custom properties, e.g.
+/// `kaishi=https://kaishi.example/ns/1.0/`.
+///
+/// # Examples
+///
+/// ```
+/// use pdf_ok::utils::XmpNamespace;
+///
+/// let namespace: XmpNamespace =
+///     "kaishi=https://kaishi.example/ns/1.0/".parse().unwrap();
+/// assert_eq!(namespace.prefix, "kaishi");
+/// assert!("dc=https://example.com/".parse::<XmpNamespace>().is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XmpNamespace {
+    /// The prefix properties in the namespace are written with.
+    pub prefix: String,
+    /// The namespace URI.
+    pub uri: String,
+}
+
+impl FromStr for XmpNamespace {
+    type Err = String;
+
+    /// Reads a namespace written `prefix=uri`. The built-in `dc`, `xmp`
+    /// and `pdf` prefixes cannot be redeclared.
+    fn from_str(declaration: &str) -> Result<Self, Self::Err> {
+        let (prefix, uri) = declaration
+            .split_once('=')
+            .map(|(prefix, uri)| (prefix.trim(), uri.trim()))
+            .filter(|(prefix, uri)| is_xml_name(prefix) && !uri.is_empty())
+            .ok_or_else(|| {
+                format!(
+                    "invalid XMP namespace \"{}\" (expected prefix=uri)",
+                    declaration
+                )
+            })?;
+        if is_built_in_prefix(prefix) || RESERVED_PREFIXES.contains(&prefix) {
+            return Err(format!(
+                "the XMP namespace prefix `{}` is reserved",
+                prefix
+            ));
+        }
+
+        Ok(XmpNamespace { prefix: prefix.to_string(), uri: uri.to_string() })
+    }
+}
+
+/// Maps a front matter key onto a custom XMP property, e.g.
+/// `kaishi:permalink=permalink`.
+///
+/// The prefix is either one of the built-in `dc`, `xmp` and `pdf`
+/// prefixes or one declared with an [`XmpNamespace`]. The properties
+/// [`xmp_packet`] derives from the Info dictionary, such as `dc:title`,
+/// cannot be mapped; set the Info dictionary entry instead.
+///
+/// # Examples
+///
+/// ```
+/// use pdf_ok::utils::XmpProperty;
+///
+/// let property: XmpProperty = "kaishi:permalink=permalink".parse().unwrap();
+/// assert_eq!(property.prefix, "kaishi");
+/// assert_eq!(property.name, "permalink");
+/// assert_eq!(property.yaml_entry, "permalink");
+/// assert!("dc:title=headline".parse::<XmpProperty>().is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XmpProperty {
+    /// The namespace prefix of the property.
+    pub prefix: String,
+    /// The name of the property within its namespace.
+    pub name: String,
+    /// The front matter key its value is read from.
+    pub yaml_entry: String,
+}
+
+impl XmpProperty {
+    /// Returns the value of the property for a document, or `None` if
+    /// the front matter key is missing, null or empty.
+    pub fn value(&self, front_matter: &FrontMatter) -> Option<String> {
+        resolve_path(front_matter, &self.yaml_entry)
+            .map(|value| format_value(value, &TemplateOptions::default()))
+            .filter(|value| !value.trim().is_empty())
+    }
+
+    /// The qualified name of the property, e.g. `kaishi:permalink`.
+    pub fn qualified_name(&self) -> String {
+        format!("{}:{}", self.prefix, self.name)
+    }
+
+    // Whether the packet already writes the property from the Info
+    // dictionary.
+    fn is_derived(&self) -> bool {
+        DERIVED_PROPERTIES.contains(&self.qualified_name().as_str())
+    }
+}
+
+impl FromStr for XmpProperty {
+    type Err = String;
+
+    /// Reads a mapping written `prefix:name=front_matter_key`.
+    fn from_str(mapping: &str) -> Result<Self, Self::Err> {
+        let property = mapping
+            .split_once('=')
+            .and_then(|(qualified_name, yaml_entry)| {
+                let (prefix, name) = qualified_name.trim().split_once(':')?;
+                let yaml_entry = yaml_entry.trim();
+                (is_xml_name(prefix) && is_xml_name(name) && !yaml_entry.is_empty())
+                    .then(|| XmpProperty {
+                        prefix: prefix.to_string(),
+                        name: name.to_string(),
+                        yaml_entry: yaml_entry.to_string(),
+                    })
+            })
+            .ok_or_else(|| {
+                format!(
+                    "invalid XMP property \"{}\" (expected prefix:name=front_matter_key)",
+                    mapping
+                )
+            })?;
+        if property.is_derived() {
+            return Err(format!(
+                "the XMP property `{}` is written from the Info dictionary",
+                property.qualified_name()
+            ));
+        }
+
+        Ok(property)
+    }
+}
+
+/// Builds an XMP metadata packet.
+///
+/// The standard properties are taken from the final values of the
+/// document information dictionary, so the two always agree:
+///
+/// * `dc:title` from `Title`
+/// * `dc:creator` from `Author`
+/// * `dc:description` from `Subject`
+/// * `dc:subject` and `pdf:Keywords` from `Keywords`, split on commas
+///   and semicolons for `dc:subject`
+/// * `dc:language` from `Language`
+/// * `xmp:CreateDate` and `xmp:ModifyDate` from `CreationDate` and
+///   `ModDate`
+/// * `xmp:CreatorTool` from `Creator`
+/// * `pdf:Producer` from `Producer`
+///
+/// Missing values are left out. The custom properties follow, each
+/// written with the namespace its prefix is declared with; properties
+/// whose prefix is not declared, or that would repeat one of the
+/// properties above, are left out.
+///
+/// # Arguments
+///
+/// * `info` - The Info dictionary values, by key.
+/// * `custom_values` - The custom properties and their values.
+/// * `namespaces` - The custom namespaces.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::BTreeMap;
+///
+/// use pdf_ok::utils::{xmp_packet, XmpNamespace, XmpProperty};
+///
+/// let info = BTreeMap::from([
+///     ("Title".to_string(), "Kaishi & friends".to_string()),
+///     ("Keywords".to_string(), "rust, pdf".to_string()),
+///     ("CreationDate".to_string(), "D:20240220151515Z".to_string()),
+/// ]);
+/// let namespace: XmpNamespace =
+///     "kaishi=https://kaishi.example/ns/1.0/".parse().unwrap();
+/// let property: XmpProperty = "kaishi:permalink=permalink".parse().unwrap();
+///
+/// let packet = xmp_packet(&info, &[(&property, "/features/".to_string())], &[namespace]);
+/// assert!(packet.contains("<rdf:li xml:lang=\"x-default\">Kaishi &amp; friends</rdf:li>"));
+/// assert!(packet.contains("<rdf:li>rust</rdf:li><rdf:li>pdf</rdf:li>"));
+/// assert!(packet.contains("<pdf:Keywords>rust, pdf</pdf:Keywords>"));
+/// assert!(packet.contains("<xmp:CreateDate>2024-02-20T15:15:15Z</xmp:CreateDate>"));
+/// assert!(packet.contains("xmlns:kaishi=\"https://kaishi.example/ns/1.0/\""));
+/// assert!(packet.contains("<kaishi:permalink>/features/</kaishi:permalink>"));
+///
+/// let info = BTreeMap::from([("Title".to_string(), "Bell\u{7}".to_string())]);
+/// assert!(xmp_packet(&info, &[], &[]).contains(">Bell</rdf:li>"));
+/// ```
+pub fn xmp_packet(
+    info: &BTreeMap<String, String>,
+    custom_values: &[(&XmpProperty, String)],
+    namespaces: &[XmpNamespace],
+) -> String {
+    let info_value = |key: &str| {
+        info.get(key).map(String::as_str).filter(|value| !value.is_empty())
+    };
+    let mut properties = Vec::new();
+
+    if let Some(title) = info_value("Title") {
+        properties.push(language_alternative("dc:title", title));
+    }
+    if let Some(author) = info_value("Author") {
+        properties.push(array("dc:creator", "rdf:Seq", [author]));
+    }
+    if let Some(subject) = info_value("Subject") {
+        properties.push(language_alternative("dc:description", subject));
+    }
+    if let Some(keywords) = info_value("Keywords") {
+        let subjects = keywords
+            .split([',', ';'])
+            .map(str::trim)
+            .filter(|keyword| !keyword.is_empty());
+        properties.push(array("dc:subject", "rdf:Bag", subjects));
+    }
+    if let Some(language) = info_value("Language") {
+        properties.push(array("dc:language", "rdf:Bag", [language]));
+    }
+    for (info_key, property) in
+        [("CreationDate", "xmp:CreateDate"), ("ModDate", "xmp:ModifyDate")]
+    {
+        if let Some(date) = info_value(info_key).and_then(parse_pdf_date) {
+            let date = date.to_rfc3339_opts(SecondsFormat::Secs, true);
+            properties.push(simple(property, &date));
+        }
+    }
+    if let Some(creator) = info_value("Creator") {
+        properties.push(simple("xmp:CreatorTool", creator));
+    }
+    if let Some(producer) = info_value("Producer") {
+        properties.push(simple("pdf:Producer", producer));
+    }
+    if let Some(keywords) = info_value("Keywords") {
+        properties.push(simple("pdf:Keywords", keywords));
+    }
+
+    let mut declarations: Vec<(&str, &str)> = BUILT_IN_NAMESPACES.to_vec();
+    for (property, value) in custom_values {
+        if property.is_derived() {
+            continue;
+        }
+        let namespace = namespaces
+            .iter()
+            .find(|namespace| namespace.prefix == property.prefix);
+        if let Some(namespace) = namespace {
+            if !declarations.iter().any(|(prefix, _)| *prefix == namespace.prefix)
+            {
+                declarations.push((&namespace.prefix, &namespace.uri));
+            }
+        } else if !is_built_in_prefix(&property.prefix) {
+            continue;
+        }
+        properties.push(simple(&property.qualified_name(), value));
+    }
+
+    let declarations: String = declarations
+        .iter()
+        .map(|(prefix, uri)| {
+            format!("\n    xmlns:{}=\"{}\"", prefix, escape_xml(uri))
+        })
+        .collect();
+    let properties: String = properties
+        .iter()
+        .map(|property| format!("   {}\n", property))
+        .collect();
+
+    format!(
+        "<?xpacket begin=\"\u{FEFF}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n\
+         <x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n \
+         <rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\n  \
+         <rdf:Description rdf:about=\"\"{}>\n\
+         {}  \
+         </rdf:Description>\n \
+         </rdf:RDF>\n\
+         </x:xmpmeta>\n\
+         <?xpacket end=\"w\"?>",
+        declarations, properties
+    )
+}
+
+// Writes a property with a single text value.
+fn simple(name: &str, value: &str) -> String {
+    format!("<{0}>{1}</{0}>", name, escape_xml(value))
+}
+
+// Writes a property whose value is a language alternative with only the
+// default language.
+fn language_alternative(name: &str, value: &str) -> String {
+    format!(
+        "<{0}><rdf:Alt><rdf:li xml:lang=\"x-default\">{1}</rdf:li></rdf:Alt></{0}>",
+        name,
+        escape_xml(value)
+    )
+}
+
+// Writes a property whose value is an ordered (`rdf:Seq`) or unordered
+// (`rdf:Bag`) array.
+fn array<'a>(
+    name: &str,
+    kind: &str,
+    values: impl IntoIterator<Item = &'a str>,
+) -> String {
+    let items: String = values
+        .into_iter()
+        .map(|value| format!("<rdf:li>{}</rdf:li>", escape_xml(value)))
+        .collect();
+
+    format!("<{0}><{1}>{2}</{1}></{0}>", name, kind, items)
+}
+
+// Escapes the characters with a special meaning in XML text and
+// attributes, and drops those XML 1.0 does not allow at all, such as
+// most control characters.
+fn escape_xml(text: &str) -> String {
+    text.chars()
+        .filter(|character| is_xml_char(*character))
+        .collect::<String>()
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+// Whether a character may appear in an XML 1.0 document.
+fn is_xml_char(character: char) -> bool {
+    matches!(
+        character,
+        '\t' | '\n' | '\r'
+            | '\u{20}'..='\u{D7FF}'
+            | '\u{E000}'..='\u{FFFD}'
+            | '\u{10000}'..
+    )
+}
+
+fn is_built_in_prefix(prefix: &str) -> bool {
+    BUILT_IN_NAMESPACES.iter().any(|(built_in, _)| *built_in == prefix)
+}
+
+// Whether text is a valid XML name without a colon, as namespace
+// prefixes and property names must be.
+fn is_xml_name(text: &str) -> bool {
+    let mut characters = text.chars();
+    characters
+        .next()
+        .is_some_and(|first| first.is_alphabetic() || first == '_')
+        && characters.all(|character| {
+            character.is_alphanumeric() || matches!(character, '_' | '-' | '.')
+        })
+}
@@ -0,0 +1,71 @@
+//! Regression suite for Markdown rendering.
+//!
+//! Each `tests/fixtures/markdown/<name>.md` file is rendered with the
+//! default options and compared with `<name>.html` next to it. To add a
+//! case, add both files.
+use std::fs;
+use std::path::Path;
+
+use pdf_ok::utils::{render_markdown, MarkdownOptions};
+
+#[test]
+fn markdown_fixtures_render_to_expected_html() {
+    let fixtures_directory =
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/markdown");
+    let mut fixture_paths: Vec<_> = fs::read_dir(&fixtures_directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "md"))
+        .collect();
+    fixture_paths.sort();
+    assert!(!fixture_paths.is_empty(), "no Markdown fixtures found");
+
+    let mut failures = Vec::new();
+    for markdown_path in &fixture_paths {
+        let markdown = fs::read_to_string(markdown_path).unwrap();
+        let expected_html =
+            fs::read_to_string(markdown_path.with_extension("html"))
+                .unwrap_or_else(|_| {
+                    panic!("{} has no .html file", markdown_path.display())
+                });
+
+        let html = render_markdown(&markdown, &MarkdownOptions::default());
+        if html != expected_html {
+            failures.push(format!(
+                "{}\n--- expected ---\n{}--- actual ---\n{}",
+                markdown_path.display(),
+                expected_html,
+                html
+            ));
+        }
+    }
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+#[test]
+fn disabled_extensions_fall_back_to_commonmark() {
+    let options = MarkdownOptions {
+        tables: false,
+        task_lists: false,
+        strikethrough: false,
+        footnotes: false,
+        autolinks: false,
+        raw_html: false,
+    };
+
+    assert_eq!(
+        render_markdown("| a |\n| - |\n| b |", &options),
+        "<p>| a |\n| - |\n| b |</p>\n"
+    );
+    assert_eq!(render_markdown("- [x] done", &options), "<ul>\n<li>[x] done</li>\n</ul>\n");
+    assert_eq!(render_markdown("~~old~~", &options), "<p>~~old~~</p>\n");
+    assert_eq!(
+        render_markdown("see https://kaishi.one", &options),
+        "<p>see https://kaishi.one</p>\n"
+    );
+    assert_eq!(
+        render_markdown("<b>bold</b>", &options),
+        "<p><!-- raw HTML omitted -->bold<!-- raw HTML omitted --></p>\n"
+    );
+}
@@ -0,0 +1,44 @@
+//! Round-trips PDF text strings through a document saved and reloaded
+//! with lopdf, as the metadata of generated PDFs is.
+use lopdf::{dictionary, Document, Object};
+
+use pdf_ok::utils::{decode_pdf_text, pdf_text_string};
+
+#[test]
+fn info_strings_survive_a_lopdf_round_trip() {
+    let values = [
+        "Kaishi",
+        "© 2024 Kaishi",
+        "Kaishi — “Features” • 10€",
+        "10\u{A0}km",
+        "Łukasz Żółć",
+        "Ελληνικά",
+        "開始 🚀",
+        "Line\nbreak (with parentheses) and \\backslash",
+    ];
+
+    let mut document = Document::with_version("1.7");
+    let mut info = lopdf::Dictionary::new();
+    for (index, value) in values.iter().enumerate() {
+        info.set(format!("Value{}", index), pdf_text_string(value));
+    }
+    let info_id = document.add_object(info);
+    let catalog_id = document.add_object(dictionary! { "Type" => "Catalog" });
+    document.trailer.set("Root", catalog_id);
+    document.trailer.set("Info", info_id);
+
+    let mut saved = Vec::new();
+    document.save_to(&mut saved).unwrap();
+    let reloaded = Document::load_mem(&saved).unwrap();
+
+    let info_id = reloaded.trailer.get(b"Info").unwrap().as_reference().unwrap();
+    let info = reloaded.get_dictionary(info_id).unwrap();
+    for (index, value) in values.iter().enumerate() {
+        let Object::String(bytes, _) =
+            info.get(format!("Value{}", index).as_bytes()).unwrap()
+        else {
+            panic!("Value{} is not a string", index);
+        };
+        assert_eq!(decode_pdf_text(bytes), *value);
+    }
+}
@@ -0,0 +1,181 @@
+//! Checks that deterministic mode gives byte-identical PDFs for identical
+//! inputs.
+//!
+//! Chrome writes the time of printing, a random `/ID` and its own object
+//! numbering into each PDF. The fixtures stand in for two printings of
+//! the same page that differ in exactly those ways.
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use lopdf::{dictionary, Document, Object, Stream, StringFormat};
+use pdf_ok::utils::{
+    content_hash, generate_pdf, post_process_pdf, DocumentMetadata,
+    FrontMatter, FrontMatterValue, MetadataOptions, PageSetup, Renderer,
+    PDF_PRODUCER,
+};
+
+// Builds a one-page PDF as Chrome would print it at `timestamp`, adding
+// its objects in reverse order when `reverse_objects` is set.
+fn printed_pdf(timestamp: &str, id: &[u8], reverse_objects: bool) -> Vec<u8> {
+    let mut doc = Document::with_version("1.4");
+    let font = || {
+        dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+        }
+    };
+    let content = || {
+        Stream::new(
+            dictionary! {},
+            b"BT /F1 24 Tf 72 720 Td (Hello) Tj ET".to_vec(),
+        )
+    };
+    let (font_id, content_id) = if reverse_objects {
+        let content_id = doc.add_object(content());
+        (doc.add_object(font()), content_id)
+    } else {
+        let font_id = doc.add_object(font());
+        (font_id, doc.add_object(content()))
+    };
+
+    let pages_id = doc.new_object_id();
+    let page_id = doc.add_object(dictionary! {
+        "Type" => "Page",
+        "Parent" => pages_id,
+        "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+        "Resources" => dictionary! { "Font" => dictionary! { "F1" => font_id } },
+        "Contents" => content_id,
+    });
+    doc.objects.insert(
+        pages_id,
+        dictionary! {
+            "Type" => "Pages",
+            "Kids" => vec![page_id.into()],
+            "Count" => 1,
+        }
+        .into(),
+    );
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    let info_id = doc.add_object(dictionary! {
+        "Creator" => Object::string_literal("Chromium"),
+        "Producer" => Object::string_literal("Skia/PDF m120"),
+        "CreationDate" => Object::string_literal(timestamp),
+        "ModDate" => Object::string_literal(timestamp),
+    });
+    doc.trailer.set("Root", catalog_id);
+    doc.trailer.set("Info", info_id);
+    doc.trailer.set(
+        "ID",
+        vec![
+            Object::String(id.to_vec(), StringFormat::Hexadecimal),
+            Object::String(id.to_vec(), StringFormat::Hexadecimal),
+        ],
+    );
+
+    let mut pdf = Vec::new();
+    doc.save_to(&mut pdf).unwrap();
+    pdf
+}
+
+fn front_matter() -> FrontMatter {
+    BTreeMap::from([
+        ("title".to_string(), FrontMatterValue::String("Hello".to_string())),
+        ("language".to_string(), FrontMatterValue::String("en".to_string())),
+    ])
+}
+
+fn deterministic_options() -> MetadataOptions {
+    MetadataOptions { deterministic: true, ..MetadataOptions::default() }
+}
+
+#[test]
+fn deterministic_post_processing_gives_identical_bytes() {
+    let first = printed_pdf("D:20240220151515+00'00'", b"first run", false);
+    let second = printed_pdf("D:20240221090000+00'00'", b"second run", true);
+    assert_ne!(content_hash(&[&first]), content_hash(&[&second]));
+
+    let source = Path::new("hello.md");
+    let options = deterministic_options();
+    let first =
+        post_process_pdf(&first, &front_matter(), &options, source).unwrap();
+    let second =
+        post_process_pdf(&second, &front_matter(), &options, source).unwrap();
+
+    assert_eq!(content_hash(&[&first]), content_hash(&[&second]));
+}
+
+#[test]
+fn deterministic_post_processing_still_reflects_the_content() {
+    let pdf = printed_pdf("D:20240220151515+00'00'", b"run", false);
+    let source = Path::new("hello.md");
+    let options = deterministic_options();
+
+    let mut changed = front_matter();
+    changed.insert(
+        "title".to_string(),
+        FrontMatterValue::String("Goodbye".to_string()),
+    );
+    let first =
+        post_process_pdf(&pdf, &front_matter(), &options, source).unwrap();
+    let second = post_process_pdf(&pdf, &changed, &options, source).unwrap();
+
+    assert_ne!(content_hash(&[&first]), content_hash(&[&second]));
+}
+
+#[test]
+fn deterministic_post_processing_ignores_mapped_producer_and_dates() {
+    let pdf = printed_pdf("D:20240220151515+00'00'", b"run", false);
+    let mut options = deterministic_options();
+    for mapping in ["Producer=title", "CreationDate=title", "ModDate=title"] {
+        options.set_doc_info_entry(mapping.parse().unwrap());
+    }
+
+    let pdf =
+        post_process_pdf(&pdf, &front_matter(), &options, Path::new("hello.md"))
+            .unwrap();
+
+    let doc = Document::load_mem(&pdf).unwrap();
+    assert_eq!(doc.metadata("Producer").as_deref(), Some(PDF_PRODUCER));
+    for key in ["CreationDate", "ModDate"] {
+        assert_eq!(doc.metadata(key).as_deref(), Some("D:19700101000000+00'00'"));
+    }
+}
+
+#[test]
+fn rendering_twice_with_chrome_gives_identical_bytes() {
+    // Chrome is not installed everywhere the tests run
+    if let Err(error) = headless_chrome::browser::default_executable() {
+        eprintln!("skipping: no Chrome executable found ({})", error);
+        return;
+    }
+
+    let output_directory = std::env::temp_dir().join("pdf_ok_reproducible");
+    let renderer = Renderer::new();
+    let html = "<html><body><h1>Hello</h1></body></html>";
+
+    let hashes: Vec<String> = ["first.pdf", "second.pdf"]
+        .iter()
+        .map(|file_name| {
+            let pdf_path = output_directory.join(file_name);
+            generate_pdf(
+                &renderer,
+                html.to_string(),
+                Path::new("hello.md"),
+                &pdf_path,
+                front_matter(),
+                &PageSetup::default(),
+                &deterministic_options(),
+            )
+            .unwrap();
+            content_hash(&[&fs::read(pdf_path).unwrap()])
+        })
+        .collect();
+    renderer.shutdown();
+
+    assert_eq!(hashes[0], hashes[1]);
+}